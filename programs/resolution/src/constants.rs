@@ -2,3 +2,103 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+#[constant]
+pub const MAX_APPROVERS: u8 = 5;
+
+/// Most approvers the registry can hold
+#[constant]
+pub const MAX_REGISTRY_SIZE: u8 = 64;
+
+/// Most programs the approver program allowlist can hold
+#[constant]
+pub const MAX_APPROVER_PROGRAMS: u8 = 16;
+
+/// What approval weights have to add up to, so a weight reads as a percentage
+#[constant]
+pub const TOTAL_APPROVAL_WEIGHT: u8 = 100;
+
+#[constant]
+pub const MAX_NOTE_LEN: u8 = 100;
+
+#[constant]
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Vote accounts store the commission as a whole percentage
+#[constant]
+pub const BPS_PER_PERCENT: u16 = 100;
+
+/// One billion SOL in lamports, more than the total supply
+#[constant]
+pub const MAX_STAKE_AMOUNT: u64 = 1_000_000_000 * 1_000_000_000;
+
+#[constant]
+pub const MAX_TITLE_LEN: u8 = 64;
+
+#[constant]
+pub const MAX_TEXT_LEN: u16 = 256;
+
+/// Byte limit of the extended text kept in the optional details PDA
+#[constant]
+pub const MAX_DETAILS_LEN: u16 = 2048;
+
+#[constant]
+pub const MAX_MILESTONES: u8 = 4;
+
+/// Covers both owner and approver granted extensions
+#[constant]
+pub const MAX_EXTENSIONS: u8 = 3;
+
+/// One day, in seconds
+#[constant]
+pub const MIN_LOCKUP_DURATION: i64 = 24 * 60 * 60;
+
+/// Two years, in seconds
+#[constant]
+pub const MAX_LOCKUP_DURATION: i64 = 2 * 365 * 24 * 60 * 60;
+
+/// Thirty days, in seconds
+#[constant]
+pub const MAX_GRACE_PERIOD: i64 = 30 * 24 * 60 * 60;
+
+/// Leading byte of get_resolution_summary's output, bumped whenever its layout changes
+#[constant]
+pub const RESOLUTION_SUMMARY_VERSION: u8 = 1;
+
+/// One day, in seconds
+#[constant]
+pub const MAX_CREATION_COOLDOWN: i64 = 24 * 60 * 60;
+
+/// Seven days, in seconds. How long a flag holds back an approved close
+#[constant]
+pub const DISPUTE_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+/// One year, in seconds
+#[constant]
+pub const MAX_VAULT_DURATION: i64 = 365 * 24 * 60 * 60;
+
+/// Resolution categories, stored as `category` on the resolution account
+#[constant]
+pub const CATEGORY_OTHER: u8 = 0;
+
+#[constant]
+pub const CATEGORY_FITNESS: u8 = 1;
+
+#[constant]
+pub const CATEGORY_LEARNING: u8 = 2;
+
+#[constant]
+pub const CATEGORY_HEALTH: u8 = 3;
+
+#[constant]
+pub const CATEGORY_FINANCE: u8 = 4;
+
+#[constant]
+pub const CATEGORY_CAREER: u8 = 5;
+
+#[constant]
+pub const CATEGORY_CREATIVE: u8 = 6;
+
+/// Number of known categories, anything at or above this is rejected
+#[constant]
+pub const CATEGORY_COUNT: u8 = 7;
@@ -1,9 +1,9 @@
 use anchor_lang::prelude::*;
 
-#[error_code]
+// Starts at 6001 since the unused CustomError placeholder that held 6000 was removed,
+// so every existing error keeps its code
+#[error_code(offset = 6001)]
 pub enum ResolutionErrorCode {
-    #[msg("Custom error message")]
-    CustomError,
     #[msg("Invalid Stake Program")]
     InvalidStakeProgram,
     #[msg("Invalid Vote Account")]
@@ -22,4 +22,156 @@ pub enum ResolutionErrorCode {
     InvalidResolutionSignature,
     #[msg("Lockup in force")]
     LockupInForce,
+    #[msg("Invalid approval threshold")]
+    InvalidThreshold,
+    #[msg("Invalid lockup duration")]
+    InvalidLockupDuration,
+    #[msg("Resolution expired")]
+    ResolutionExpired,
+    #[msg("Invalid stake amount")]
+    InvalidStakeAmount,
+    #[msg("Stake is already deactivating")]
+    AlreadyDeactivating,
+    #[msg("Stake amount is below the minimum delegation")]
+    StakeAmountTooLow,
+    #[msg("Stake amount is above the maximum")]
+    StakeAmountTooHigh,
+    #[msg("Approval note is too long")]
+    NoteTooLong,
+    #[msg("Resolution can only be cancelled in the stake activation epoch")]
+    CancelWindowClosed,
+    #[msg("Invalid charity")]
+    InvalidCharity,
+    #[msg("Invalid penalty")]
+    InvalidPenalty,
+    #[msg("No rewards to claim")]
+    NoRewardsToClaim,
+    #[msg("Invalid title")]
+    InvalidTitle,
+    #[msg("Invalid text")]
+    InvalidText,
+    #[msg("Invalid withdraw amount")]
+    InvalidWithdrawAmount,
+    #[msg("Stake is still active, deactivate it and wait for the cooldown")]
+    StakeNotDeactivated,
+    #[msg("Stake has already been split")]
+    StakeAlreadySplit,
+    #[msg("Invalid split amount")]
+    InvalidSplitAmount,
+    #[msg("Stake account already exists, use a fresh keypair")]
+    StakeAccountAlreadyInitialized,
+    #[msg("Invalid withdraw destination")]
+    InvalidWithdrawDestination,
+    #[msg("Stake deactivation only takes effect next epoch, close after the cooldown")]
+    DeactivationPending,
+    #[msg("Creating resolutions is paused")]
+    ProgramPaused,
+    #[msg("Resolution has already been extended the maximum number of times")]
+    TooManyExtensions,
+    #[msg("Validator doesn't appear to be voting")]
+    DelinquentValidator,
+    #[msg("Account isn't a writable resolution of the given owner")]
+    InvalidResolutionAccount,
+    #[msg("Invalid category")]
+    InvalidCategory,
+    #[msg("Stake isn't deactivating in the current epoch")]
+    StakeNotDeactivating,
+    #[msg("Invalid commission cap")]
+    InvalidCommissionCap,
+    #[msg("Validator commission is above the resolution's cap")]
+    CommissionTooHigh,
+    #[msg("Resolution has no stake attached")]
+    NoStakeAttached,
+    #[msg("Invalid grace period")]
+    InvalidGracePeriod,
+    #[msg("Custodian doesn't match the resolution's lockup custodian")]
+    InvalidCustodian,
+    #[msg("Invalid approver reward")]
+    InvalidApproverReward,
+    #[msg("Resolution doesn't hold enough lamports for the approver rewards")]
+    InsufficientRewardEscrow,
+    #[msg("Approver account missing from the remaining accounts")]
+    MissingApproverAccount,
+    #[msg("Resolution details account has to be passed")]
+    MissingDetailsAccount,
+    #[msg("Stake account wasn't fully drained")]
+    StakeNotDrained,
+    #[msg("Approver isn't in the registry")]
+    ApproverNotRegistered,
+    #[msg("Registry is full")]
+    RegistryFull,
+    #[msg("Invalid stake pool program")]
+    InvalidStakePoolProgram,
+    #[msg("Stake pool doesn't match the resolution's stake pool")]
+    InvalidStakePool,
+    #[msg("Stake lockup doesn't match the resolution's end time")]
+    LockupMismatch,
+    #[msg("No extension proposal is pending")]
+    NoPendingExtension,
+    #[msg("Invalid milestones")]
+    InvalidMilestones,
+    #[msg("No milestone left to release")]
+    NoPendingMilestone,
+    #[msg("Milestone time hasn't been reached")]
+    MilestoneNotReached,
+    #[msg("Stored bump doesn't match the re-derived bump")]
+    BumpMismatch,
+    #[msg("Creation cooldown hasn't passed since the owner's last resolution")]
+    CreationCooldown,
+    #[msg("Invalid creation cooldown")]
+    InvalidCreationCooldown,
+    #[msg("Delegate can't be the owner, an approver or another approver's delegate")]
+    InvalidDelegate,
+    #[msg("Close has already been initiated")]
+    CloseAlreadyRequested,
+    #[msg("Close hasn't been initiated")]
+    CloseNotRequested,
+    #[msg("Stake is still deactivating, finalize after the deactivation epoch")]
+    DeactivationEpochNotReached,
+    #[msg("Account isn't a resolution in the first release's layout")]
+    InvalidLegacyResolution,
+    #[msg("Approver list is already full")]
+    ApproverListFull,
+    #[msg("Approver pool has fewer eligible members than requested")]
+    ApproverPoolTooSmall,
+    #[msg("Slot hashes sysvar has no entries")]
+    NoSlotHashes,
+    #[msg("Stake account was closed outside the program, use close_orphaned_resolution")]
+    StakeAccountClosed,
+    #[msg("Stake account still exists")]
+    StakeAccountNotClosed,
+    #[msg("Invalid approval validity")]
+    InvalidApprovalValidity,
+    #[msg("Close can't run in the same transaction as a deactivation of the same resolution")]
+    ConflictingInstructions,
+    #[msg("Invalid vault duration")]
+    InvalidVaultDuration,
+    #[msg("Vault account has to be passed")]
+    MissingVaultAccount,
+    #[msg("Resolution has a vault attached")]
+    VaultAttached,
+    #[msg("Nothing has unlocked since the last claim")]
+    NothingToClaim,
+    #[msg("Resolution is flagged, an approved close has to wait out the dispute window")]
+    ResolutionFlagged,
+    #[msg("Resolution is already flagged")]
+    AlreadyFlagged,
+    #[msg("Resolution isn't flagged by the signer")]
+    NotFlagged,
+    #[msg(
+        "Approval weights must be non-zero, one per approver and add up to TOTAL_APPROVAL_WEIGHT"
+    )]
+    InvalidWeights,
+    #[msg("Validator's withdraw authority belongs to the owner")]
+    SelfValidatorNotAllowed,
+    #[msg("Stake accounts have different authorities, lockups or validators")]
+    StakeAccountsNotMergeable,
+    #[msg("Program is already on the approver program allowlist")]
+    ApproverProgramAlreadyAllowed,
+    #[msg("Program isn't on the approver program allowlist")]
+    ApproverProgramNotAllowed,
+    #[msg("Approver program allowlist is full")]
+    ApproverProgramsFull,
+    #[msg("Instruction has to be invoked through a CPI")]
+    CpiRequired,
 }
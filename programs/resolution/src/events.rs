@@ -0,0 +1,325 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct ResolutionInitialized {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub stake_account: Pubkey,
+    pub stake_lamports: u64,
+    pub end_time: i64,
+    pub created_slot: u64,
+    pub id: u64,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub authority: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct CreationCooldownUpdated {
+    pub authority: Pubkey,
+    pub creation_cooldown: i64,
+}
+
+#[event]
+pub struct RegistryUpdated {
+    pub authority: Pubkey,
+    pub approver: Pubkey,
+    /// False when the approver was removed
+    pub registered: bool,
+}
+
+#[event]
+pub struct ApproverProgramUpdated {
+    pub authority: Pubkey,
+    pub program: Pubkey,
+    /// False when the program was removed
+    pub allowed: bool,
+}
+
+#[event]
+pub struct ResolutionRenewed {
+    pub previous_resolution: Pubkey,
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64,
+}
+
+#[event]
+pub struct ResolutionMigrated {
+    pub legacy_resolution: Pubkey,
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64,
+}
+
+#[event]
+pub struct ResolutionFlagged {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub flagged_by: Pubkey,
+    pub dispute_ends_at: i64,
+}
+
+#[event]
+pub struct FlagCleared {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub flagged_by: Pubkey,
+}
+
+#[event]
+pub struct ApproverReplaced {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub old_approver: Pubkey,
+    pub new_approver: Pubkey,
+}
+
+#[event]
+pub struct ApprovalDelegateSet {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+    /// None when the approver revoked their delegate
+    pub delegate: Option<Pubkey>,
+}
+
+#[event]
+pub struct ApproverRemovalConsented {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+    pub target: Pubkey,
+}
+
+#[event]
+pub struct ApprovalValidityUpdated {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approval_validity: i64,
+}
+
+#[event]
+pub struct ApproverAdded {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+    pub required_approvals: u8,
+}
+
+#[event]
+pub struct ApproverRemoved {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+}
+
+#[event]
+pub struct TextUpdated {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct DetailsAttached {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct VaultOpened {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub duration: i64,
+}
+
+#[event]
+pub struct CharityUpdated {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub charity: Pubkey,
+    pub penalty_bps: u16,
+}
+
+#[event]
+pub struct ResolutionApproved {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+    pub approved_at: i64,
+}
+
+#[event]
+pub struct EmergencyReleaseApproved {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+}
+
+#[event]
+pub struct ApprovalRevoked {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+}
+
+#[event]
+pub struct LockupExtended {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub end_time: i64,
+    pub extension_count: u8,
+}
+
+#[event]
+pub struct ExtensionApproved {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub approver: Pubkey,
+    pub extension_seconds: i64,
+}
+
+#[event]
+pub struct ExtensionCancelled {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub extension_seconds: i64,
+}
+
+#[event]
+pub struct StakeAdded {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub additional_amount: u64,
+    pub stake_amount: u64,
+}
+
+#[event]
+pub struct MilestoneReleased {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StakePoolDeposited {
+    pub resolution: Pubkey,
+    pub stake_pool: Pubkey,
+    pub lamports: u64,
+    pub pool_tokens: u64,
+}
+
+#[event]
+pub struct StakeRedelegated {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub validator_vote_account: Pubkey,
+}
+
+#[event]
+pub struct StakeSplit {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub second_stake_account: Pubkey,
+    pub validator_vote_account: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StakeMerged {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub stake_account: Pubkey,
+    pub merged_stake_account: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PartialWithdrawal {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub resolution: Pubkey,
+    pub new_resolution: Pubkey,
+    pub owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct CloseInitiated {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub requested_at: i64,
+    pub deactivation_epoch: u64,
+}
+
+#[event]
+pub struct OrphanedResolutionClosed {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub stake_account: Pubkey,
+}
+
+#[event]
+pub struct StakeDeactivated {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub stake_account: Pubkey,
+}
+
+#[event]
+pub struct StakeReactivated {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub stake_account: Pubkey,
+}
+
+#[event]
+pub struct ResolutionClosed {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    /// Whether the approved path ran, emergency releases included
+    pub was_approved: bool,
+    pub withdraw_amount: u64,
+    pub penalty_amount: u64,
+    pub burn_amount: u64,
+    pub approver_rewards: u64,
+}
+
+#[event]
+pub struct VaultClaimed {
+    pub vault: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+}
+
+#[event]
+pub struct ResolutionCancelled {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub withdraw_amount: u64,
+}
+
+#[event]
+pub struct DustSwept {
+    pub resolution: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
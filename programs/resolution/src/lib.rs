@@ -1,22 +1,38 @@
 pub mod constants;
 pub mod error;
+pub mod events;
+pub mod stake_pool;
+
+use std::collections::BTreeSet;
 
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{
+    ed25519_program,
+    hash::hashv,
     incinerator,
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+    log::sol_log_data,
     program::{invoke, invoke_signed},
     stake::{
         self,
-        instruction::{deactivate_stake, delegate_stake, initialize, withdraw},
+        instruction::{
+            authorize, deactivate_stake, delegate_stake, initialize, merge, set_lockup, split,
+            withdraw, LockupArgs,
+        },
         state::StakeStateV2,
-        state::{Authorized, Lockup},
+        state::{Authorized, Lockup, StakeAuthorize},
+        tools::get_minimum_delegation,
     },
-    system_instruction::create_account,
-    vote::{self},
+    system_instruction::{create_account, transfer},
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    vote::{self, state::VoteState},
 };
+use anchor_lang::Discriminator;
+use anchor_spl::token::{close_account, CloseAccount, Mint, Token, TokenAccount};
 
 pub use constants::*;
 use error::ResolutionErrorCode;
+use events::*;
 
 declare_id!("6nGFQ3vek6SST6AhmfCeii2pj7GK2yiF2FkgeJUvXJPU");
 
@@ -25,11 +41,189 @@ pub mod resolution {
 
     use super::*;
 
+    // Whoever calls this first becomes the authority that can pause new resolutions
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.paused = false;
+        config.creation_cooldown = 0;
+        config.bump = ctx.bumps.config;
+
+        Ok(())
+    }
+
+    // Pausing only blocks new resolutions, existing ones can still be approved and closed
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+
+        emit!(PauseUpdated {
+            authority: ctx.accounts.authority.key(),
+            paused,
+        });
+
+        Ok(())
+    }
+
+    // Minimum time between two resolutions of the same owner, 0 turns the cooldown off
+    pub fn set_creation_cooldown(ctx: Context<SetPaused>, creation_cooldown: i64) -> Result<()> {
+        if !(0..=MAX_CREATION_COOLDOWN).contains(&creation_cooldown) {
+            return Err(ResolutionErrorCode::InvalidCreationCooldown.into());
+        }
+
+        ctx.accounts.config.creation_cooldown = creation_cooldown;
+
+        emit!(CreationCooldownUpdated {
+            authority: ctx.accounts.authority.key(),
+            creation_cooldown,
+        });
+
+        Ok(())
+    }
+
+    // Whoever calls this first becomes the authority that curates the approver registry
+    pub fn initialize_registry(ctx: Context<InitializeRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.authority = ctx.accounts.authority.key();
+        registry.approvers = [].to_vec();
+        registry.bump = ctx.bumps.registry;
+
+        Ok(())
+    }
+
+    // Only resolutions created with require_registered_approvers look at the registry
+    pub fn add_to_registry(ctx: Context<UpdateRegistry>, approver: Pubkey) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+
+        if registry.approvers.contains(&approver) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+        if registry.approvers.len() >= MAX_REGISTRY_SIZE as usize {
+            return Err(ResolutionErrorCode::RegistryFull.into());
+        }
+
+        registry.approvers.push(approver);
+
+        emit!(RegistryUpdated {
+            authority: ctx.accounts.authority.key(),
+            approver,
+            registered: true,
+        });
+
+        Ok(())
+    }
+
+    // Existing resolutions keep an approver that's removed here, only new ones are affected
+    pub fn remove_from_registry(ctx: Context<UpdateRegistry>, approver: Pubkey) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+
+        let index = registry
+            .approvers
+            .iter()
+            .position(|registered| registered == &approver)
+            .ok_or(ResolutionErrorCode::ApproverNotRegistered)?;
+        registry.approvers.swap_remove(index);
+
+        emit!(RegistryUpdated {
+            authority: ctx.accounts.authority.key(),
+            approver,
+            registered: false,
+        });
+
+        Ok(())
+    }
+
+    // Whoever calls this first becomes the authority that curates which programs may
+    // approve through approve_via_cpi
+    pub fn initialize_approver_programs(ctx: Context<InitializeApproverPrograms>) -> Result<()> {
+        let approver_programs = &mut ctx.accounts.approver_programs;
+        approver_programs.authority = ctx.accounts.authority.key();
+        approver_programs.programs = [].to_vec();
+        approver_programs.bump = ctx.bumps.approver_programs;
+
+        Ok(())
+    }
+
+    pub fn add_approver_program(
+        ctx: Context<UpdateApproverPrograms>,
+        program: Pubkey,
+    ) -> Result<()> {
+        let approver_programs = &mut ctx.accounts.approver_programs;
+
+        if approver_programs.programs.contains(&program) {
+            return Err(ResolutionErrorCode::ApproverProgramAlreadyAllowed.into());
+        }
+        if approver_programs.programs.len() >= MAX_APPROVER_PROGRAMS as usize {
+            return Err(ResolutionErrorCode::ApproverProgramsFull.into());
+        }
+
+        approver_programs.programs.push(program);
+
+        emit!(ApproverProgramUpdated {
+            authority: ctx.accounts.authority.key(),
+            program,
+            allowed: true,
+        });
+
+        Ok(())
+    }
+
+    // Approvals a removed program already recorded stay, only new ones are rejected
+    pub fn remove_approver_program(
+        ctx: Context<UpdateApproverPrograms>,
+        program: Pubkey,
+    ) -> Result<()> {
+        let approver_programs = &mut ctx.accounts.approver_programs;
+
+        let index = approver_programs
+            .programs
+            .iter()
+            .position(|allowed| allowed == &program)
+            .ok_or(ResolutionErrorCode::ApproverProgramNotAllowed)?;
+        approver_programs.programs.swap_remove(index);
+
+        emit!(ApproverProgramUpdated {
+            authority: ctx.accounts.authority.key(),
+            program,
+            allowed: false,
+        });
+
+        Ok(())
+    }
+
+    // Bootstraps the global counters, resolutions can't be created before this ran
+    pub fn initialize_stats(ctx: Context<InitializeStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.stats;
+        stats.total_locked = 0;
+        stats.active_count = 0;
+        stats.next_id = 0;
+        stats.bump = ctx.bumps.stats;
+
+        Ok(())
+    }
+
+    // nonce lets an owner hold several resolutions at once, each under its own PDA
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_resolution(
         ctx: Context<InitializeResolution>,
+        nonce: u64,
         stake_amount: u64,
         lockup_duration: i64,
+        title: String,
         text: String,
+        threshold: u8,
+        charity: Pubkey,
+        penalty_bps: u16,
+        category: u8,
+        withdraw_destination: Option<Pubkey>,
+        max_commission_bps: Option<u16>,
+        grace_period: i64,
+        custodian: Option<Pubkey>,
+        approver_reward: u64,
+        require_registered_approvers: bool,
+        milestones: Vec<i64>,
+        milestone_releases: Vec<u64>,
+        weights: Vec<u8>,
+        disallow_self_validator: bool,
     ) -> Result<()> {
         let approvers: Vec<Pubkey> = ctx
             .remaining_accounts
@@ -37,397 +231,7577 @@ pub mod resolution {
             .map(|account| account.key())
             .collect();
 
-        if approvers.len() != 3 {
-            return Err(ResolutionErrorCode::InvalidNumApprovers.into());
-        }
+        create_resolution(
+            ctx.accounts,
+            &ctx.bumps,
+            nonce,
+            approvers,
+            stake_amount,
+            lockup_duration,
+            title,
+            text,
+            threshold,
+            charity,
+            penalty_bps,
+            category,
+            withdraw_destination,
+            max_commission_bps,
+            grace_period,
+            custodian,
+            approver_reward,
+            require_registered_approvers,
+            milestones,
+            milestone_releases,
+            weights,
+            disallow_self_validator,
+        )
+    }
 
-        // owner shouldn't be in the approvers list
-        if approvers.contains(&ctx.accounts.owner.key()) {
-            return Err(ResolutionErrorCode::InvalidApprover.into());
+    // Same as initialize_resolution, but the lockup end is an absolute unix timestamp so
+    // the client's clock doesn't matter. It still has to fall within the lockup bounds
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_resolution_at(
+        ctx: Context<InitializeResolution>,
+        nonce: u64,
+        stake_amount: u64,
+        end_timestamp: i64,
+        title: String,
+        text: String,
+        threshold: u8,
+        charity: Pubkey,
+        penalty_bps: u16,
+        category: u8,
+        withdraw_destination: Option<Pubkey>,
+        max_commission_bps: Option<u16>,
+        grace_period: i64,
+        custodian: Option<Pubkey>,
+        approver_reward: u64,
+        require_registered_approvers: bool,
+        milestones: Vec<i64>,
+        milestone_releases: Vec<u64>,
+        weights: Vec<u8>,
+        disallow_self_validator: bool,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if end_timestamp <= now {
+            return Err(ResolutionErrorCode::InvalidLockupDuration.into());
         }
 
-        // check if all approvers are unique
-        let mut unique_approvers = approvers.clone();
-        unique_approvers.sort();
-        unique_approvers.dedup();
-        if unique_approvers.len() != approvers.len() {
-            return Err(ResolutionErrorCode::InvalidApprover.into());
+        let approvers: Vec<Pubkey> = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| account.key())
+            .collect();
+
+        // create_resolution adds this back onto the same clock, so end_time == end_timestamp
+        create_resolution(
+            ctx.accounts,
+            &ctx.bumps,
+            nonce,
+            approvers,
+            stake_amount,
+            end_timestamp - now,
+            title,
+            text,
+            threshold,
+            charity,
+            penalty_bps,
+            category,
+            withdraw_destination,
+            max_commission_bps,
+            grace_period,
+            custodian,
+            approver_reward,
+            require_registered_approvers,
+            milestones,
+            milestone_releases,
+            weights,
+            disallow_self_validator,
+        )
+    }
+
+    // Same as initialize_resolution, but approvers are typed accounts so the IDL documents them
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_resolution_v2(
+        ctx: Context<InitializeResolutionV2>,
+        nonce: u64,
+        stake_amount: u64,
+        lockup_duration: i64,
+        title: String,
+        text: String,
+        threshold: u8,
+        charity: Pubkey,
+        penalty_bps: u16,
+        category: u8,
+        withdraw_destination: Option<Pubkey>,
+        max_commission_bps: Option<u16>,
+        grace_period: i64,
+        custodian: Option<Pubkey>,
+        approver_reward: u64,
+        require_registered_approvers: bool,
+        milestones: Vec<i64>,
+        milestone_releases: Vec<u64>,
+        weights: Vec<u8>,
+        disallow_self_validator: bool,
+    ) -> Result<()> {
+        let approvers = vec![
+            ctx.accounts.approver_a.key(),
+            ctx.accounts.approver_b.key(),
+            ctx.accounts.approver_c.key(),
+        ];
+
+        create_resolution(
+            &mut ctx.accounts.base,
+            &ctx.bumps.base,
+            nonce,
+            approvers,
+            stake_amount,
+            lockup_duration,
+            title,
+            text,
+            threshold,
+            charity,
+            penalty_bps,
+            category,
+            withdraw_destination,
+            max_commission_bps,
+            grace_period,
+            custodian,
+            approver_reward,
+            require_registered_approvers,
+            milestones,
+            milestone_releases,
+            weights,
+            disallow_self_validator,
+        )
+    }
+
+    // Same as initialize_resolution, but num_approvers approvers are drawn from the registry,
+    // seeded by the most recent slot hash. The slot's leader can influence that hash, so this
+    // isn't cryptographically secure randomness, only good enough for low-stakes selection
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_resolution_random(
+        ctx: Context<InitializeResolutionRandom>,
+        nonce: u64,
+        stake_amount: u64,
+        lockup_duration: i64,
+        title: String,
+        text: String,
+        num_approvers: u8,
+        threshold: u8,
+        charity: Pubkey,
+        penalty_bps: u16,
+        category: u8,
+        withdraw_destination: Option<Pubkey>,
+        max_commission_bps: Option<u16>,
+        grace_period: i64,
+        custodian: Option<Pubkey>,
+        approver_reward: u64,
+        require_registered_approvers: bool,
+        milestones: Vec<i64>,
+        milestone_releases: Vec<u64>,
+        disallow_self_validator: bool,
+    ) -> Result<()> {
+        let owner = ctx.accounts.base.owner.key();
+        let destination = withdraw_destination.unwrap_or(owner);
+
+        // leave out anyone create_resolution would reject as an approver
+        let candidates: Vec<Pubkey> = ctx
+            .accounts
+            .approver_pool
+            .approvers
+            .iter()
+            .filter(|approver| ![owner, charity, destination].contains(*approver))
+            .copied()
+            .collect();
+
+        if num_approvers as usize > candidates.len() {
+            return Err(ResolutionErrorCode::ApproverPoolTooSmall.into());
         }
 
-        let now = Clock::get()?.unix_timestamp;
-        let lockup_end = now + lockup_duration;
+        let seed = hashv(&[
+            &most_recent_slot_hash(&ctx.accounts.slot_hashes)?,
+            owner.as_ref(),
+            &nonce.to_le_bytes(),
+        ])
+        .to_bytes();
+        let approvers = pick_approvers(&candidates, num_approvers as usize, seed);
 
-        // Both stake_authority and withdraw_authority are the owner
-        let authorized = Authorized {
-            staker: ctx.accounts.resolution_account.key(),
-            withdrawer: ctx.accounts.owner.key(),
-        };
+        create_resolution(
+            &mut ctx.accounts.base,
+            &ctx.bumps.base,
+            nonce,
+            approvers,
+            stake_amount,
+            lockup_duration,
+            title,
+            text,
+            threshold,
+            charity,
+            penalty_bps,
+            category,
+            withdraw_destination,
+            max_commission_bps,
+            grace_period,
+            custodian,
+            approver_reward,
+            require_registered_approvers,
+            milestones,
+            milestone_releases,
+            Vec::new(),
+            disallow_self_validator,
+        )
+    }
 
-        // Configure Lockup for stake account
-        // Set custodian to resolution PDA account
-        let lockup = Lockup {
-            unix_timestamp: lockup_end,
-            epoch: 0,
-            custodian: ctx.accounts.resolution_account.key(),
-        };
+    // Same as initialize_resolution, but the SOL is deposited into an SPL stake pool instead of
+    // being delegated to a single validator. The pool tokens are held by the resolution PDA, so
+    // they stay locked just like a stake account would
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_resolution_pool(
+        ctx: Context<InitializeResolutionPool>,
+        nonce: u64,
+        stake_amount: u64,
+        lockup_duration: i64,
+        title: String,
+        text: String,
+        threshold: u8,
+        charity: Pubkey,
+        penalty_bps: u16,
+        category: u8,
+        withdraw_destination: Option<Pubkey>,
+        grace_period: i64,
+        approver_reward: u64,
+        require_registered_approvers: bool,
+    ) -> Result<()> {
+        if stake_amount == 0 {
+            return Err(ResolutionErrorCode::StakeAmountTooLow.into());
+        }
 
-        // Calculate balance for stake account
-        let rent = Rent::get()?;
-        let stake_space = StakeStateV2::size_of();
-        let lamports = rent
-            .minimum_balance(stake_space)
-            .saturating_add(stake_amount);
+        if stake_amount > MAX_STAKE_AMOUNT {
+            return Err(ResolutionErrorCode::StakeAmountTooHigh.into());
+        }
+
+        let approvers: Vec<Pubkey> = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| account.key())
+            .collect();
+
+        // Created without a stake account, the pool does the delegating. The commission cap
+        // and lockup custodian don't apply since there is no stake account to guard
+        create_resolution(
+            &mut ctx.accounts.base,
+            &ctx.bumps.base,
+            nonce,
+            approvers,
+            0,
+            lockup_duration,
+            title,
+            text,
+            threshold,
+            charity,
+            penalty_bps,
+            category,
+            withdraw_destination,
+            None,
+            grace_period,
+            None,
+            approver_reward,
+            require_registered_approvers,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            false,
+        )?;
 
+        // The resolution's own token account doubles as the referrer so referral fees stay locked too
         invoke(
-            &create_account(
-                &ctx.accounts.owner.key,
-                &ctx.accounts.stake_account.key,
-                lamports,
-                stake_space as u64,
-                &stake::program::ID,
+            &stake_pool::deposit_sol(
+                &ctx.accounts.stake_pool.key(),
+                &ctx.accounts.pool_withdraw_authority.key(),
+                &ctx.accounts.reserve_stake.key(),
+                &ctx.accounts.base.owner.key(),
+                &ctx.accounts.pool_token_account.key(),
+                &ctx.accounts.manager_fee_account.key(),
+                &ctx.accounts.pool_token_account.key(),
+                &ctx.accounts.pool_mint.key(),
+                &ctx.accounts.token_program.key(),
+                stake_amount,
             ),
             &[
-                ctx.accounts.owner.to_account_info(),
-                ctx.accounts.stake_account.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.stake_pool.to_account_info(),
+                ctx.accounts.pool_withdraw_authority.to_account_info(),
+                ctx.accounts.reserve_stake.to_account_info(),
+                ctx.accounts.base.owner.to_account_info(),
+                ctx.accounts.pool_token_account.to_account_info(),
+                ctx.accounts.manager_fee_account.to_account_info(),
+                ctx.accounts.pool_mint.to_account_info(),
+                ctx.accounts.base.system_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
             ],
         )?;
 
-        // Initialize stake account
-        invoke(
-            &initialize(&ctx.accounts.stake_account.key, &authorized, &lockup),
-            &[
-                ctx.accounts.stake_account.to_account_info(),
-                ctx.accounts.rent.to_account_info(),
-            ],
+        ctx.accounts.pool_token_account.reload()?;
+        let pool_tokens = ctx.accounts.pool_token_account.amount;
+
+        // stake_amount is still the principal close_pool_resolution returns on the not-approved path
+        let resolution = &mut ctx.accounts.base.resolution_account;
+        resolution.stake_amount = stake_amount;
+        resolution.stake_pool = Some(ctx.accounts.stake_pool.key());
+        resolution.pool_token_account = Some(ctx.accounts.pool_token_account.key());
+
+        ctx.accounts.base.stats.total_locked = ctx
+            .accounts
+            .base
+            .stats
+            .total_locked
+            .saturating_add(stake_amount);
+
+        emit!(StakePoolDeposited {
+            resolution: resolution.key(),
+            stake_pool: ctx.accounts.stake_pool.key(),
+            lamports: stake_amount,
+            pool_tokens,
+        });
+
+        Ok(())
+    }
+
+    // Starts the same goal again under a new nonce. The goal itself (text, approvers, category
+    // and the rest of the settings) carries over, while the stake, lockup and approvals start
+    // fresh. The previous resolution has to still be open since closing wipes it
+    pub fn renew_resolution(
+        ctx: Context<RenewResolution>,
+        nonce: u64,
+        stake_amount: u64,
+        lockup_duration: i64,
+    ) -> Result<()> {
+        let previous = (*ctx.accounts.previous_resolution).clone();
+
+        create_resolution(
+            &mut ctx.accounts.base,
+            &ctx.bumps.base,
+            nonce,
+            previous.approvers,
+            stake_amount,
+            lockup_duration,
+            previous.title,
+            previous.text,
+            previous.required_approvals,
+            previous.charity,
+            previous.penalty_bps,
+            previous.category,
+            Some(previous.withdraw_destination),
+            previous.max_commission_bps,
+            previous.grace_period,
+            previous.custodian,
+            previous.approver_reward,
+            previous.require_registered_approvers,
+            // milestones are absolute timestamps, the old ones don't fit a new lockup
+            Vec::new(),
+            Vec::new(),
+            previous.weights,
+            previous.disallow_self_validator,
         )?;
 
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"resolution",
-            ctx.accounts.owner.key.as_ref(),
-            &[ctx.bumps.resolution_account],
-        ]];
+        emit!(ResolutionRenewed {
+            previous_resolution: ctx.accounts.previous_resolution.key(),
+            resolution: ctx.accounts.base.resolution_account.key(),
+            owner: ctx.accounts.base.owner.key(),
+            id: ctx.accounts.base.resolution_account.id,
+        });
+
+        Ok(())
+    }
+
+    // Moves a resolution created by the first release, which lived at `["resolution", owner]`
+    // in a much smaller layout, to `["resolution", owner, nonce]` in the current one. The
+    // address changes with the seeds, so the stake's staker and lockup custodian are handed
+    // from the legacy PDA to the new one and the legacy account is closed to the owner
+    pub fn migrate_resolution(ctx: Context<MigrateResolution>, nonce: u64) -> Result<()> {
+        let legacy_info = ctx.accounts.legacy_resolution.to_account_info();
+        let legacy =
+            LegacyResolutionAccount::try_from_account_data(&legacy_info.try_borrow_data()?)?;
+
+        let owner = ctx.accounts.owner.key();
+        if legacy.owner != owner {
+            return Err(ResolutionErrorCode::InvalidLegacyResolution.into());
+        }
+        if legacy.bump != ctx.bumps.legacy_resolution {
+            return Err(ResolutionErrorCode::BumpMismatch.into());
+        }
+        if legacy.stake_account != ctx.accounts.stake_account.key() {
+            return Err(ResolutionErrorCode::InvalidStakeAccount.into());
+        }
+
+        let legacy_key = legacy_info.key();
+        let resolution_key = ctx.accounts.resolution_account.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"resolution", owner.as_ref(), &[legacy.bump]]];
 
-        // Delegate stake
         invoke_signed(
-            &delegate_stake(
-                &ctx.accounts.stake_account.key,
-                &ctx.accounts.resolution_account.key(),
-                &ctx.accounts.validator_vote_account.key,
+            &authorize(
+                &ctx.accounts.stake_account.key(),
+                &legacy_key,
+                &resolution_key,
+                StakeAuthorize::Staker,
+                None,
             ),
             &[
                 ctx.accounts.stake_account.to_account_info(),
-                ctx.accounts.validator_vote_account.to_account_info(),
                 ctx.accounts.clock.to_account_info(),
-                ctx.accounts.stake_history.to_account_info(),
-                ctx.accounts.stake_config.to_account_info(),
-                ctx.accounts.resolution_account.to_account_info(),
+                legacy_info.clone(),
             ],
             signer_seeds,
         )?;
 
-        let resolution = &mut ctx.accounts.resolution_account;
+        // Once the lockup is over the stake program ignores the custodian, and would want the
+        // withdrawer rather than the legacy PDA to sign a change
+        let clock = &ctx.accounts.clock;
+        if clock.unix_timestamp < legacy.end_time {
+            invoke_signed(
+                &set_lockup(
+                    &ctx.accounts.stake_account.key(),
+                    &LockupArgs {
+                        unix_timestamp: None,
+                        epoch: None,
+                        custodian: Some(resolution_key),
+                    },
+                    &legacy_key,
+                ),
+                &[
+                    ctx.accounts.stake_account.to_account_info(),
+                    legacy_info.clone(),
+                ],
+                signer_seeds,
+            )?;
+        }
 
-        resolution.owner = ctx.accounts.owner.key();
-        resolution.text = text;
-        resolution.approvers = approvers;
-        resolution.approved_by = [].to_vec();
-        resolution.stake_amount = stake_amount;
-        resolution.stake_account = ctx.accounts.stake_account.key();
-        resolution.start_time = now;
-        resolution.end_time = lockup_end;
-        resolution.bump = ctx.bumps.resolution_account;
+        let validator_vote_account = stake_state(&ctx.accounts.stake_account)?
+            .delegation()
+            .map_or(Pubkey::default(), |delegation| delegation.voter_pubkey);
+        let id = ctx.accounts.stats.record_opened(legacy.stake_amount);
+
+        ctx.accounts
+            .resolution_account
+            .set_inner(ResolutionAccount::from_legacy(
+                legacy,
+                nonce,
+                ctx.bumps.resolution_account,
+                id,
+                validator_vote_account,
+                clock,
+            ));
+
+        // the legacy account isn't an Account<ResolutionAccount>, so it's closed by hand
+        ctx.accounts.owner.add_lamports(legacy_info.lamports())?;
+        legacy_info.sub_lamports(legacy_info.lamports())?;
+        legacy_info.realloc(0, false)?;
+        legacy_info.assign(&System::id());
+
+        emit!(ResolutionMigrated {
+            legacy_resolution: legacy_key,
+            resolution: resolution_key,
+            owner,
+            id,
+        });
 
         Ok(())
     }
 
-    pub fn approve_resolution(ctx: Context<ApproveResolution>) -> Result<()> {
+    // An empty note means the approver left no comment.
+    // Approving twice fails with AlreadyApproved, see approve_resolution_idempotent
+    pub fn approve_resolution(ctx: Context<ApproveResolution>, note: String) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        // a registered delegate approves in the name of the approver it stands in for
+        let approver = ctx
+            .accounts
+            .resolution_account
+            .approver_for(&ctx.accounts.signer.key());
+
+        record_approval(&mut ctx.accounts.resolution_account, approver, note)
+    }
+
+    // Lets an approver name a backup key that can approve on their behalf, e.g. while
+    // they're away. None revokes the current delegate
+    pub fn set_approval_delegate(
+        ctx: Context<ApproveResolution>,
+        delegate: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
         let resolution = &mut ctx.accounts.resolution_account;
+        let approver = ctx.accounts.signer.key();
 
-        // check if the signer is in the approvers list
-        if !resolution.approvers.contains(&ctx.accounts.signer.key()) {
+        if !resolution.approvers.contains(&approver) {
             return Err(ResolutionErrorCode::InvalidApprover.into());
         }
 
-        if resolution.approved_by.contains(&ctx.accounts.signer.key()) {
-            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        resolution
+            .approval_delegates
+            .retain(|entry| entry.approver != approver);
+
+        if let Some(delegate) = delegate {
+            // each delegate maps back to exactly one approver, and the owner can't vouch
+            // for their own goal through someone else's approval
+            if delegate == resolution.owner
+                || resolution.approvers.contains(&delegate)
+                || resolution
+                    .approval_delegates
+                    .iter()
+                    .any(|entry| entry.delegate == delegate)
+            {
+                return Err(ResolutionErrorCode::InvalidDelegate.into());
+            }
+
+            resolution
+                .approval_delegates
+                .push(ApprovalDelegate { approver, delegate });
         }
 
-        resolution.approved_by.push(ctx.accounts.signer.key());
+        emit!(ApprovalDelegateSet {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approver,
+            delegate,
+        });
 
         Ok(())
     }
 
-    pub fn deactivate_resolution_stake(ctx: Context<DeactivateResolutionStake>) -> Result<()> {
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"resolution",
-            ctx.accounts.owner.key.as_ref(),
-            &[ctx.bumps.resolution_account],
-        ]];
+    // Lets the owner submit an approval the approver signed off-chain, so the approver
+    // doesn't pay fees. The Ed25519 program instruction right before this one has to carry
+    // the approver's signature over approval_message, which binds it to this resolution,
+    // its approval_nonce, the approver's current approval (so a stale one can't be renewed
+    // by replaying the signature that gave it) and the note
+    pub fn approve_with_signature(
+        ctx: Context<ApproveWithSignature>,
+        approver: Pubkey,
+        note: String,
+    ) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
 
-        invoke_signed(
-            &deactivate_stake(
-                &ctx.accounts.stake_account.key(),
-                &ctx.accounts.resolution_account.key(),
-            ),
-            &[
-                ctx.accounts.stake_account.to_account_info(),
-                ctx.accounts.clock.to_account_info(),
-                ctx.accounts.resolution_account.to_account_info(),
-            ],
-            signer_seeds,
+        let resolution = &ctx.accounts.resolution_account;
+        let message = approval_message(
+            &resolution.key(),
+            resolution.approval_nonce,
+            resolution.approved_at_of(&approver),
+            &note,
+        );
+        verify_ed25519_signature(&ctx.accounts.instructions_sysvar, &approver, &message)?;
+
+        record_approval(&mut ctx.accounts.resolution_account, approver, note)
+    }
+
+    // For approvers that are PDAs of another program, e.g. a DAO approving through its own
+    // governance. The PDA has to sign, which only its program can do through invoke_signed,
+    // and the transaction's top-level instruction has to belong to an allowlisted program
+    pub fn approve_via_cpi(ctx: Context<ApproveViaCpi>, note: String) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        ensure_invoked_by_approver_program(
+            &ctx.accounts.instructions_sysvar,
+            &ctx.accounts.approver_programs.programs,
         )?;
 
-        Ok(())
+        record_approval(
+            &mut ctx.accounts.resolution_account,
+            ctx.accounts.approver.key(),
+            note,
+        )
     }
 
-    pub fn close_resolution(ctx: Context<CloseResolution>) -> Result<()> {
+    // Same as approve_resolution, but an approval that's already recorded is a no-op
+    // instead of an AlreadyApproved error, so retried submissions don't need special handling.
+    // A stale approval still goes through approve_resolution to get renewed
+    pub fn approve_resolution_idempotent(
+        ctx: Context<ApproveResolution>,
+        note: String,
+    ) -> Result<()> {
         let now = Clock::get()?.unix_timestamp;
+        let resolution = &ctx.accounts.resolution_account;
+        if resolution.has_valid_approval(&resolution.approver_for(&ctx.accounts.signer.key()), now)
+        {
+            return Ok(());
+        }
+
+        approve_resolution(ctx, note)
+    }
+
+    // For approvers looking after several resolutions of the same owner, e.g. a multisig
+    // that only wants to sign once. The resolutions come in as writable remaining accounts.
+    // Ones the signer can't approve right now (not an approver, already approved, past the
+    // grace period) are skipped instead of failing the batch, but an account that isn't a
+    // resolution of the owner fails it, since that's a malformed transaction
+    pub fn batch_approve<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchApprove<'info>>,
+        note: String,
+    ) -> Result<()> {
+        if note.len() > MAX_NOTE_LEN as usize {
+            return Err(ResolutionErrorCode::NoteTooLong.into());
+        }
+
+        let owner = ctx.accounts.owner.key();
+        let signer = ctx.accounts.signer.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut approved = 0u32;
+        for account_info in ctx.remaining_accounts {
+            let mut resolution = load_owned_resolution(account_info, &owner)?;
+
+            let approver = resolution.approver_for(&signer);
+            if !resolution.can_approve(&approver, now) {
+                msg!("Skipped: {}", account_info.key);
+                continue;
+            }
+
+            record_approval(&mut resolution, approver, note.clone())?;
+            // not part of the Accounts struct, so nothing writes it back on its own
+            resolution.exit(&crate::ID)?;
+            approved += 1;
+        }
+
+        msg!("Approved: {}", approved);
+
+        Ok(())
+    }
+
+    // Independent of the goal: once every approver has signed off, close_resolution
+    // takes the approved path even though the lockup hasn't ended
+    pub fn approve_emergency_release(ctx: Context<ApproveEmergencyRelease>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
 
-        let resolution_key = ctx.accounts.resolution_account.key();
         let resolution = &mut ctx.accounts.resolution_account;
 
-        let is_approved =
-            resolution.approved_by.len() >= resolution.approvers.len().try_into().unwrap();
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
 
-        // If resolution is not yet approved,
-        // then it's not possible to close the resolution before the end time
-        if !is_approved && now < resolution.end_time {
-            return Err(ResolutionErrorCode::LockupInForce.into());
+        if !resolution.approvers.contains(&ctx.accounts.signer.key()) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
         }
 
-        // Calculate withdraw amount and burn amount
-        // If approved, withdraw all stake account balance
-        // If not approved, withdraw the stake amount and burn the rest (rewards+rent)
-        let (withdraw_amount, burn_amount) = if is_approved {
-            (ctx.accounts.stake_account.lamports(), 0 as u64)
-        } else {
-            (
-                resolution.stake_amount,
-                ctx.accounts.stake_account.lamports() - resolution.stake_amount,
-            )
-        };
+        if resolution
+            .emergency_release_approved_by
+            .contains(&ctx.accounts.signer.key())
+        {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
 
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"resolution",
-            ctx.accounts.owner.key.as_ref(),
-            &[ctx.bumps.resolution_account],
-        ]];
+        resolution
+            .emergency_release_approved_by
+            .push(ctx.accounts.signer.key());
 
-        msg!("Withdraw amount: {}", withdraw_amount);
+        emit!(EmergencyReleaseApproved {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approver: ctx.accounts.signer.key(),
+        });
 
-        match is_approved {
-            true => {
-                invoke_signed(
-                    &withdraw(
-                        &ctx.accounts.stake_account.key(),
-                        &ctx.accounts.owner.key(),
-                        &ctx.accounts.owner.key(),
-                        withdraw_amount,
-                        Some(&resolution_key),
-                    ),
-                    &[
-                        ctx.accounts.stake_account.to_account_info(),
-                        ctx.accounts.owner.to_account_info(),
-                        ctx.accounts.clock.to_account_info(),
-                        ctx.accounts.stake_history.to_account_info(),
-                        ctx.accounts.owner.to_account_info(),
-                        ctx.accounts.resolution_account.to_account_info(),
-                    ],
-                    signer_seeds,
-                )?;
-            }
-            false => {
-                invoke(
-                    &withdraw(
-                        &ctx.accounts.stake_account.key(),
-                        &ctx.accounts.owner.key(),
-                        &ctx.accounts.owner.key(),
-                        withdraw_amount,
-                        None,
-                    ),
-                    &[
-                        ctx.accounts.stake_account.to_account_info(),
-                        ctx.accounts.owner.to_account_info(),
-                        ctx.accounts.clock.to_account_info(),
-                        ctx.accounts.stake_history.to_account_info(),
-                        ctx.accounts.owner.to_account_info(),
-                    ],
-                )?;
-            }
+        Ok(())
+    }
+
+    // Only reachable while the resolution is open, since close_resolution closes the PDA
+    pub fn revoke_approval(ctx: Context<RevokeApproval>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        // check if the signer is in the approvers list
+        if !resolution.approvers.contains(&ctx.accounts.signer.key()) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
         }
 
-        msg!("Burn amount: {}", burn_amount);
+        // signer must have approved before they can revoke
+        let index = resolution
+            .approved_by
+            .iter()
+            .position(|approver| approver == &ctx.accounts.signer.key())
+            .ok_or(ResolutionErrorCode::InvalidApprover)?;
 
-        if burn_amount > 0 {
-            invoke_signed(
-                &withdraw(
-                    &ctx.accounts.stake_account.key(),
-                    &ctx.accounts.owner.key(),
-                    &ctx.accounts.incinerator_account.key(),
-                    burn_amount,
-                    Some(&resolution_key),
+        // approval_notes and approved_at are index-aligned with approved_by
+        resolution.approved_by.remove(index);
+        resolution.approval_notes.remove(index);
+        resolution.approved_at.remove(index);
+        // off-chain signatures collected so far can't be replayed to undo the revocation
+        resolution.approval_nonce += 1;
+
+        emit!(ApprovalRevoked {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approver: ctx.accounts.signer.key(),
+        });
+
+        Ok(())
+    }
+
+    // An approver who thinks the goal wasn't really met can hold back the approved payout for
+    // DISPUTE_WINDOW. The flag clears when its approver retracts it or lapses once the window
+    // ends, and every approver gets a single flag so the close can't be stalled forever
+    pub fn flag_resolution(ctx: Context<RevokeApproval>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let signer = ctx.accounts.signer.key();
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        resolution.record_flag(signer, now)?;
+
+        emit!(ResolutionFlagged {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            flagged_by: signer,
+            dispute_ends_at: now + DISPUTE_WINDOW,
+        });
+
+        Ok(())
+    }
+
+    // The approver stays in flaggers so they can't flag again after retracting
+    pub fn clear_flag(ctx: Context<RevokeApproval>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        if !resolution.flagged || resolution.flagged_by != ctx.accounts.signer.key() {
+            return Err(ResolutionErrorCode::NotFlagged.into());
+        }
+
+        resolution.flagged = false;
+
+        emit!(FlagCleared {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            flagged_by: resolution.flagged_by,
+        });
+
+        Ok(())
+    }
+
+    // Settling a dispute from the owner's side means going back to the approvers, see
+    // ResolutionAccount::resolve_flag
+    pub fn resolve_flag(ctx: Context<UpdateText>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        resolution.resolve_flag(Clock::get()?.unix_timestamp)?;
+
+        emit!(FlagCleared {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            flagged_by: resolution.flagged_by,
+        });
+
+        Ok(())
+    }
+
+    pub fn replace_approver(ctx: Context<ReplaceApprover>, old: Pubkey, new: Pubkey) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        // once someone has vouched, the approver set is locked in
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        let index = resolution
+            .approvers
+            .iter()
+            .position(|approver| approver == &old)
+            .ok_or(ResolutionErrorCode::InvalidApprover)?;
+
+        if new == resolution.charity {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+
+        // the registry requirement set at init also holds for replacements
+        if resolution.require_registered_approvers {
+            ensure_registered(
+                ctx.accounts
+                    .registry
+                    .as_ref()
+                    .map(|registry| &registry.approvers[..]),
+                &[new],
+            )?;
+        }
+
+        let mut approvers = resolution.approvers.clone();
+        approvers[index] = new;
+        validate_approver_set(&resolution.owner, &approvers)?;
+        ensure_not_approver(&approvers, &resolution.withdraw_destination)?;
+
+        resolution.approvers = approvers;
+        // a removed approver's vote on a pending extension goes with them
+        resolution
+            .extension_approved_by
+            .retain(|approver| approver != &old);
+        resolution
+            .emergency_release_approved_by
+            .retain(|approver| approver != &old);
+        resolution
+            .milestone_approved_by
+            .retain(|approver| approver != &old);
+        resolution
+            .removal_consented_by
+            .retain(|approver| approver != &old);
+        resolution
+            .approval_delegates
+            .retain(|entry| entry.approver != old && entry.delegate != new);
+        if resolution.pending_removal == Some(old) {
+            resolution.pending_removal = None;
+            resolution.removal_consented_by = [].to_vec();
+        }
+
+        emit!(ApproverReplaced {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            old_approver: old,
+            new_approver: new,
+        });
+
+        Ok(())
+    }
+
+    // For approvers who commit after init. The threshold goes up with the list, mirroring
+    // remove_approver, so the number of approvers allowed to abstain stays the same
+    pub fn add_approver(ctx: Context<AddApprover>, approver: Pubkey) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        // once someone has vouched, the approver set is locked in
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        if resolution.approvers.len() >= MAX_APPROVERS as usize {
+            return Err(ResolutionErrorCode::ApproverListFull.into());
+        }
+
+        // there's no weight to give the newcomer without rebalancing everyone else's
+        if !resolution.weights.is_empty() {
+            return Err(ResolutionErrorCode::InvalidWeights.into());
+        }
+
+        if approver == resolution.charity {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+
+        if resolution.require_registered_approvers {
+            ensure_registered(
+                ctx.accounts
+                    .registry
+                    .as_ref()
+                    .map(|registry| &registry.approvers[..]),
+                &[approver],
+            )?;
+        }
+
+        let mut approvers = resolution.approvers.clone();
+        approvers.push(approver);
+        validate_approver_set(&resolution.owner, &approvers)?;
+        ensure_not_approver(&approvers, &resolution.withdraw_destination)?;
+
+        resolution.approvers = approvers;
+        resolution
+            .approval_delegates
+            .retain(|entry| entry.delegate != approver);
+        resolution.required_approvals += 1;
+
+        // the newcomer's reward is escrowed like everyone else's was at init, otherwise an
+        // approved close couldn't pay out once they approve too
+        if resolution.approver_reward > 0 {
+            invoke(
+                &transfer(
+                    ctx.accounts.owner.key,
+                    &resolution.key(),
+                    resolution.approver_reward,
                 ),
                 &[
-                    ctx.accounts.stake_account.to_account_info(),
-                    ctx.accounts.incinerator_account.to_account_info(),
-                    ctx.accounts.clock.to_account_info(),
-                    ctx.accounts.stake_history.to_account_info(),
                     ctx.accounts.owner.to_account_info(),
-                    ctx.accounts.resolution_account.to_account_info(),
+                    resolution.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
                 ],
-                signer_seeds,
             )?;
         }
 
+        emit!(ApproverAdded {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approver,
+            required_approvals: resolution.required_approvals,
+        });
+
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializeResolution<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    // Unblocks a resolution whose approver went missing after approvals started. Every
+    // other approver has to consent, a different target starts a fresh proposal
+    pub fn remove_approver(ctx: Context<RemoveApprover>, target: Pubkey) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
 
-    #[account(
-        init,
-        space = 8 + ResolutionAccount::INIT_SPACE,
-        payer = owner,
-        seeds = [b"resolution", owner.key().as_ref()],
-        bump
-    )]
-    pub resolution_account: Account<'info, ResolutionAccount>,
+        let resolution = &mut ctx.accounts.resolution_account;
+        let signer = ctx.accounts.signer.key();
 
-    /// CHECK: We create the stake account in the instruction hence SystemProgram will fail if it's an existing account
-    #[account(mut)]
-    pub stake_account: Signer<'info>,
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
 
-    /// CHECK: The delegate instruction should fail if not a valid Vote account
-    #[account(
-        constraint = validator_vote_account.owner == &vote::program::ID @ ResolutionErrorCode::InvalidVoteAccount
-    )]
-    pub validator_vote_account: AccountInfo<'info>,
+        if !resolution.approvers.contains(&signer) || signer == target {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
 
-    /// CHECK: We validate the stake config account
-    #[account(
-        constraint = stake_config.key() == pubkey!("StakeConfig11111111111111111111111111111111").key()
-    )]
-    pub stake_config: AccountInfo<'info>,
+        if !resolution.approvers.contains(&target) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
 
-    pub rent: Sysvar<'info, Rent>,
-    pub clock: Sysvar<'info, Clock>,
-    pub stake_history: Sysvar<'info, StakeHistory>,
+        if resolution.pending_removal != Some(target) {
+            resolution.pending_removal = Some(target);
+            resolution.removal_consented_by = [].to_vec();
+        }
 
-    /// CHECK: We validate the program ID in the instruction
-    #[account(
-        executable,
-        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
-    )]
-    pub stake_program: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+        if resolution.removal_consented_by.contains(&signer) {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
 
-#[derive(Accounts)]
-pub struct ApproveResolution<'info> {
-    #[account(mut)]
-    pub signer: Signer<'info>,
+        resolution.removal_consented_by.push(signer);
 
-    #[account()]
-    pub owner: SystemAccount<'info>,
+        emit!(ApproverRemovalConsented {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approver: signer,
+            target,
+        });
 
-    #[account(
-        mut,
-        has_one = owner,
-        seeds = [b"resolution", owner.key().as_ref()],
-        bump
-    )]
-    pub resolution_account: Account<'info, ResolutionAccount>,
-}
+        if resolution.removal_consented_by.len() < resolution.approvers.len() - 1 {
+            return Ok(());
+        }
 
-#[derive(Accounts)]
-pub struct DeactivateResolutionStake<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        // the threshold drops by what the target's approval was worth
+        let removed_weight = resolution.weight_of(&target).min(u8::MAX as u16) as u8;
+        if let Some(index) = resolution
+            .approvers
+            .iter()
+            .position(|approver| approver == &target)
+        {
+            if !resolution.weights.is_empty() {
+                resolution.weights.remove(index);
+            }
+        }
+        resolution.approvers.retain(|approver| approver != &target);
+        // approval_notes and approved_at are index-aligned with approved_by
+        if let Some(index) = resolution
+            .approved_by
+            .iter()
+            .position(|approver| approver == &target)
+        {
+            resolution.approved_by.remove(index);
+            resolution.approval_notes.remove(index);
+            resolution.approved_at.remove(index);
+        }
+        resolution
+            .extension_approved_by
+            .retain(|approver| approver != &target);
+        resolution
+            .emergency_release_approved_by
+            .retain(|approver| approver != &target);
+        resolution
+            .milestone_approved_by
+            .retain(|approver| approver != &target);
+        resolution
+            .approval_delegates
+            .retain(|entry| entry.approver != target);
+        // never down to zero, which would count as approved without anyone vouching
+        resolution.required_approvals = resolution
+            .required_approvals
+            .saturating_sub(removed_weight)
+            .max(1);
+        resolution.pending_removal = None;
+        resolution.removal_consented_by = [].to_vec();
 
-    /// CHECK: We validate using has_one and the owner of the account
-    #[account(
-        mut,
-        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
-    )]
-    pub stake_account: AccountInfo<'info>,
+        emit!(ApproverRemoved {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approver: target,
+        });
 
-    #[account(
-        mut,
-        has_one = owner,
-        has_one = stake_account,
-        seeds = [b"resolution", owner.key().as_ref()],
-        bump
-    )]
-    pub resolution_account: Account<'info, ResolutionAccount>,
+        Ok(())
+    }
 
-    pub clock: Sysvar<'info, Clock>,
+    // Makes approvals expire approval_validity seconds after they were given, 0 turns expiry
+    // off. Fixed once anyone has approved, so it can't be used to revive stale approvals
+    pub fn set_approval_validity(ctx: Context<UpdateText>, approval_validity: i64) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
 
-    /// CHECK: We validate the program ID in the instruction
-    #[account(
-        executable,
-        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
-    )]
-    pub stake_program: UncheckedAccount<'info>,
-}
+        if approval_validity < 0 {
+            return Err(ResolutionErrorCode::InvalidApprovalValidity.into());
+        }
 
-#[derive(Accounts)]
-pub struct CloseResolution<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        let resolution = &mut ctx.accounts.resolution_account;
 
-    /// CHECK: We validate using has_one and the owner of the account
-    #[account(
-        mut,
-        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
-    )]
-    pub stake_account: AccountInfo<'info>,
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
 
-    #[account(
-        mut,
-        close = owner,
-        has_one = owner,
-        has_one = stake_account,
-        seeds = [b"resolution", owner.key().as_ref()],
-        bump
-    )]
-    pub resolution_account: Account<'info, ResolutionAccount>,
+        resolution.approval_validity = approval_validity;
 
-    pub clock: Sysvar<'info, Clock>,
-    pub stake_history: Sysvar<'info, StakeHistory>,
+        emit!(ApprovalValidityUpdated {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approval_validity,
+        });
 
-    /// CHECK: We validate that the account key is the Incinerator account
-    #[account(
-        mut,
-        constraint = incinerator_account.key() == incinerator::id()
-    )]
-    pub incinerator_account: AccountInfo<'info>,
+        Ok(())
+    }
 
-    /// CHECK: We validate the program ID in the instruction
-    #[account(
-        executable,
-        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
-    )]
-    pub stake_program: UncheckedAccount<'info>,
-}
+    pub fn update_text(ctx: Context<UpdateText>, text: String) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
 
-#[account]
-#[derive(InitSpace, Debug)]
+        validate_text(&text)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        // approvers vouched for the text as it is, so it's frozen once anyone approves
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        resolution.text = text;
+
+        emit!(TextUpdated {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+        });
+
+        Ok(())
+    }
+
+    // The resolution keeps its short text, a longer description goes into a separate
+    // details PDA so base accounts stay small. Frozen once anyone approves, like the text
+    pub fn attach_details(ctx: Context<AttachDetails>, text: String) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        if text.trim().is_empty() || text.len() > MAX_DETAILS_LEN as usize {
+            return Err(ResolutionErrorCode::InvalidText.into());
+        }
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        resolution.has_details = true;
+
+        let details = &mut ctx.accounts.details;
+        details.resolution = resolution.key();
+        details.text = text;
+        details.bump = ctx.bumps.details;
+
+        emit!(DetailsAttached {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+        });
+
+        Ok(())
+    }
+
+    // A full MAX_DETAILS_LEN text doesn't fit in one transaction, so it can be sent in chunks
+    pub fn append_details(ctx: Context<AppendDetails>, text: String) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &ctx.accounts.resolution_account;
+
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        let details = &mut ctx.accounts.details;
+        if details.text.len() + text.len() > MAX_DETAILS_LEN as usize {
+            return Err(ResolutionErrorCode::InvalidText.into());
+        }
+
+        details.text.push_str(&text);
+
+        Ok(())
+    }
+
+    // Opting into a vault makes an approved close pay into a vault PDA, which then
+    // releases the payout linearly over `duration` seconds through claim_vault.
+    // Frozen once anyone approves, approvers vouch for a known payout
+    pub fn open_vault(ctx: Context<OpenVault>, duration: i64) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        if duration <= 0 || duration > MAX_VAULT_DURATION {
+            return Err(ResolutionErrorCode::InvalidVaultDuration.into());
+        }
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        // stakeless and pooled resolutions have no close_resolution payout to stream
+        if resolution.is_stakeless() || resolution.is_pooled() {
+            return Err(ResolutionErrorCode::NoStakeAttached.into());
+        }
+
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        resolution.has_vault = true;
+
+        // beneficiary, amount and start are filled in by close_resolution
+        let vault = &mut ctx.accounts.vault;
+        vault.resolution = resolution.key();
+        vault.owner = resolution.owner;
+        vault.beneficiary = Pubkey::default();
+        vault.total_amount = 0;
+        vault.claimed_amount = 0;
+        vault.start_time = 0;
+        vault.duration = duration;
+        vault.bump = ctx.bumps.vault;
+
+        emit!(VaultOpened {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            duration,
+        });
+
+        Ok(())
+    }
+
+    // Pays out whatever has unlocked since the last claim. The vault closes to the owner,
+    // who paid its rent, once everything has been claimed
+    pub fn claim_vault(ctx: Context<ClaimVault>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+
+        let claimable = vault
+            .unlocked_amount(now)
+            .saturating_sub(vault.claimed_amount);
+        if claimable == 0 {
+            return Err(ResolutionErrorCode::NothingToClaim.into());
+        }
+
+        vault.sub_lamports(claimable)?;
+        ctx.accounts.beneficiary.add_lamports(claimable)?;
+        vault.claimed_amount += claimable;
+
+        emit!(VaultClaimed {
+            vault: vault.key(),
+            beneficiary: vault.beneficiary,
+            amount: claimable,
+            claimed_amount: vault.claimed_amount,
+        });
+
+        if vault.claimed_amount == vault.total_amount {
+            vault.close(ctx.accounts.owner.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update_charity(
+        ctx: Context<UpdateCharity>,
+        charity: Pubkey,
+        penalty_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        // approvers signed up for these stakes, so where a failure pays out is frozen too
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        validate_charity(
+            &resolution.owner,
+            &resolution.approvers,
+            &charity,
+            penalty_bps,
+        )?;
+
+        resolution.charity = charity;
+        resolution.penalty_bps = penalty_bps;
+
+        emit!(CharityUpdated {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            charity,
+            penalty_bps,
+        });
+
+        Ok(())
+    }
+
+    pub fn extend_lockup(ctx: Context<ExtendLockup>, additional_duration: i64) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        if additional_duration <= 0 {
+            return Err(ResolutionErrorCode::InvalidLockupDuration.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        // Extending only makes sense while the goal is still in progress
+        if resolution.is_approved(now) {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        if now >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        // repeated extensions would make the deadline meaningless
+        if resolution.extension_count >= MAX_EXTENSIONS {
+            return Err(ResolutionErrorCode::TooManyExtensions.into());
+        }
+
+        let new_end_time = resolution
+            .end_time
+            .checked_add(additional_duration)
+            .ok_or(ResolutionErrorCode::InvalidLockupDuration)?;
+
+        // extensions can't be used to get past the maximum lockup either
+        if new_end_time - resolution.start_time > MAX_LOCKUP_DURATION {
+            return Err(ResolutionErrorCode::InvalidLockupDuration.into());
+        }
+
+        resolution.end_time = new_end_time;
+        resolution.extension_count += 1;
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        // Lockup is in force, so the stake program requires the custodian to sign
+        let (custodian_key, custodian) =
+            lockup_custodian(&ctx.accounts.resolution_account, &ctx.accounts.custodian)?;
+        let custodian_key = custodian_key.ok_or(ResolutionErrorCode::InvalidCustodian)?;
+        invoke_signed(
+            &set_lockup(
+                &ctx.accounts.stake_account.key(),
+                &LockupArgs {
+                    unix_timestamp: Some(new_end_time),
+                    epoch: None,
+                    custodian: None,
+                },
+                &custodian_key,
+            ),
+            &[ctx.accounts.stake_account.to_account_info(), custodian],
+            signer_seeds,
+        )?;
+
+        emit!(LockupExtended {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            end_time: new_end_time,
+            extension_count: ctx.accounts.resolution_account.extension_count,
+        });
+
+        Ok(())
+    }
+
+    // Approvers vote on granting more time; a different amount starts a fresh proposal
+    pub fn approve_extension(ctx: Context<ApproveExtension>, extension_seconds: i64) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        if extension_seconds <= 0 {
+            return Err(ResolutionErrorCode::InvalidLockupDuration.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        if now >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        if !resolution.approvers.contains(&ctx.accounts.signer.key()) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+
+        // approver granted extensions count towards the same limit
+        if resolution.extension_count >= MAX_EXTENSIONS {
+            return Err(ResolutionErrorCode::TooManyExtensions.into());
+        }
+
+        if resolution.pending_extension != extension_seconds {
+            resolution.pending_extension = extension_seconds;
+            resolution.extension_approved_by = [].to_vec();
+        }
+
+        if resolution
+            .extension_approved_by
+            .contains(&ctx.accounts.signer.key())
+        {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        resolution
+            .extension_approved_by
+            .push(ctx.accounts.signer.key());
+
+        emit!(ExtensionApproved {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            approver: ctx.accounts.signer.key(),
+            extension_seconds,
+        });
+
+        if resolution.total_weight(&resolution.extension_approved_by)
+            < resolution.required_approvals as u16
+        {
+            return Ok(());
+        }
+
+        let new_end_time = resolution
+            .end_time
+            .checked_add(extension_seconds)
+            .ok_or(ResolutionErrorCode::InvalidLockupDuration)?;
+
+        if new_end_time - resolution.start_time > MAX_LOCKUP_DURATION {
+            return Err(ResolutionErrorCode::InvalidLockupDuration.into());
+        }
+
+        resolution.end_time = new_end_time;
+        resolution.extension_count += 1;
+        resolution.pending_extension = 0;
+        resolution.extension_approved_by = [].to_vec();
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        // Lockup is in force, so the stake program requires the custodian to sign
+        let (custodian_key, custodian) =
+            lockup_custodian(&ctx.accounts.resolution_account, &ctx.accounts.custodian)?;
+        let custodian_key = custodian_key.ok_or(ResolutionErrorCode::InvalidCustodian)?;
+        invoke_signed(
+            &set_lockup(
+                &ctx.accounts.stake_account.key(),
+                &LockupArgs {
+                    unix_timestamp: Some(new_end_time),
+                    epoch: None,
+                    custodian: None,
+                },
+                &custodian_key,
+            ),
+            &[ctx.accounts.stake_account.to_account_info(), custodian],
+            signer_seeds,
+        )?;
+
+        emit!(LockupExtended {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            end_time: new_end_time,
+            extension_count: ctx.accounts.resolution_account.extension_count,
+        });
+
+        Ok(())
+    }
+
+    // Withdraws a pending extension proposal before enough approvers have voted on it
+    pub fn cancel_extension(ctx: Context<CancelExtension>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        if resolution.pending_extension == 0 {
+            return Err(ResolutionErrorCode::NoPendingExtension.into());
+        }
+
+        let extension_seconds = resolution.pending_extension;
+        resolution.pending_extension = 0;
+        resolution.extension_approved_by = [].to_vec();
+
+        emit!(ExtensionCancelled {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            extension_seconds,
+        });
+
+        Ok(())
+    }
+
+    // Closed resolutions can't be topped up since close_resolution closes the PDA.
+    // The lamports only land in the stake account's balance, a plain transfer doesn't
+    // touch the delegation, so the top up stays undelegated and earns nothing until it's
+    // withdrawn along with the rest at close
+    pub fn add_stake(ctx: Context<AddStake>, additional_amount: u64) -> Result<()> {
+        if additional_amount == 0 {
+            return Err(ResolutionErrorCode::InvalidStakeAmount.into());
+        }
+
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        invoke(
+            &transfer(
+                ctx.accounts.owner.key,
+                ctx.accounts.stake_account.key,
+                additional_amount,
+            ),
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // close_resolution returns stake_amount on the not-approved path,
+        // so the top up has to be tracked to avoid burning it
+        let resolution = &mut ctx.accounts.resolution_account;
+        resolution.stake_amount = resolution
+            .stake_amount
+            .checked_add(additional_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        if resolution.stake_amount > MAX_STAKE_AMOUNT {
+            return Err(ResolutionErrorCode::StakeAmountTooHigh.into());
+        }
+
+        ctx.accounts.stats.total_locked = ctx
+            .accounts
+            .stats
+            .total_locked
+            .saturating_add(additional_amount);
+
+        emit!(StakeAdded {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            additional_amount,
+            stake_amount: resolution.stake_amount,
+        });
+
+        Ok(())
+    }
+
+    // The stake program only accepts a new vote account once the stake is inactive,
+    // so the owner needs to deactivate and wait out the cooldown epoch first
+    pub fn redelegate_stake(ctx: Context<RedelegateStake>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        ensure_commission_within(
+            &ctx.accounts.validator_vote_account,
+            ctx.accounts.resolution_account.max_commission_bps,
+        )?;
+
+        let resolution = &ctx.accounts.resolution_account;
+        if resolution.disallow_self_validator {
+            ensure_not_self_validator(
+                &ctx.accounts.validator_vote_account,
+                &[resolution.owner, resolution.withdraw_destination],
+            )?;
+        }
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        invoke_signed(
+            &delegate_stake(
+                ctx.accounts.stake_account.key,
+                &ctx.accounts.resolution_account.key(),
+                ctx.accounts.validator_vote_account.key,
+            ),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.validator_vote_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.stake_config.to_account_info(),
+                ctx.accounts.resolution_account.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+        resolution.validator_vote_account = ctx.accounts.validator_vote_account.key();
+
+        emit!(StakeRedelegated {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            validator_vote_account: ctx.accounts.validator_vote_account.key(),
+        });
+
+        Ok(())
+    }
+
+    // Like redelegate_stake, delegating the split off part only works while it has no
+    // effective stake, i.e. in the activation epoch or once deactivated
+    pub fn split_stake(ctx: Context<SplitStake>, amount: u64) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &ctx.accounts.resolution_account;
+
+        // only one extra stake account is tracked
+        if resolution.second_stake_account.is_some() {
+            return Err(ResolutionErrorCode::StakeAlreadySplit.into());
+        }
+
+        if amount == 0 || amount >= resolution.stake_amount {
+            return Err(ResolutionErrorCode::InvalidSplitAmount.into());
+        }
+
+        ensure_commission_within(
+            &ctx.accounts.validator_vote_account,
+            resolution.max_commission_bps,
+        )?;
+
+        if resolution.disallow_self_validator {
+            ensure_not_self_validator(
+                &ctx.accounts.validator_vote_account,
+                &[resolution.owner, resolution.withdraw_destination],
+            )?;
+        }
+
+        // The split account has to be rent exempt on its own, the owner pays for that
+        let rent_exempt_reserve = Rent::get()?.minimum_balance(StakeStateV2::size_of());
+        invoke(
+            &transfer(
+                ctx.accounts.owner.key,
+                ctx.accounts.split_stake_account.key,
+                rent_exempt_reserve,
+            ),
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.split_stake_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        // allocate, assign and split; the split account keeps the same authorities and lockup
+        for instruction in split(
+            ctx.accounts.stake_account.key,
+            &ctx.accounts.resolution_account.key(),
+            amount,
+            ctx.accounts.split_stake_account.key,
+        ) {
+            invoke_signed(
+                &instruction,
+                &[
+                    ctx.accounts.stake_account.to_account_info(),
+                    ctx.accounts.split_stake_account.to_account_info(),
+                    ctx.accounts.resolution_account.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                signer_seeds,
+            )?;
+        }
+
+        invoke_signed(
+            &delegate_stake(
+                ctx.accounts.split_stake_account.key,
+                &ctx.accounts.resolution_account.key(),
+                ctx.accounts.validator_vote_account.key,
+            ),
+            &[
+                ctx.accounts.split_stake_account.to_account_info(),
+                ctx.accounts.validator_vote_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.stake_config.to_account_info(),
+                ctx.accounts.resolution_account.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+        resolution.second_stake_account = Some(ctx.accounts.split_stake_account.key());
+
+        emit!(StakeSplit {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            second_stake_account: ctx.accounts.split_stake_account.key(),
+            validator_vote_account: ctx.accounts.validator_vote_account.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    // Folds the split off stake account back into the main one, which keeps its delegation.
+    // The stake program only merges compatible states: both inactive, or both activating or
+    // active on the same validator. Stake split to another validator has to be deactivated
+    // and cooled down first
+    pub fn merge_stake(ctx: Context<MergeStake>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution_key = ctx.accounts.resolution_account.key();
+
+        for stake_account in [
+            &ctx.accounts.stake_account,
+            &ctx.accounts.second_stake_account,
+        ] {
+            ensure_staked_by(stake_account, &resolution_key)?;
+        }
+
+        ensure_mergeable(
+            &stake_state(&ctx.accounts.stake_account)?,
+            &stake_state(&ctx.accounts.second_stake_account)?,
+        )?;
+
+        let merged_lamports = ctx.accounts.second_stake_account.lamports();
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        // the source account is drained and deinitialized by the stake program
+        for instruction in merge(
+            ctx.accounts.stake_account.key,
+            ctx.accounts.second_stake_account.key,
+            &resolution_key,
+        ) {
+            invoke_signed(
+                &instruction,
+                &[
+                    ctx.accounts.stake_account.to_account_info(),
+                    ctx.accounts.second_stake_account.to_account_info(),
+                    ctx.accounts.clock.to_account_info(),
+                    ctx.accounts.stake_history.to_account_info(),
+                    ctx.accounts.resolution_account.to_account_info(),
+                ],
+                signer_seeds,
+            )?;
+        }
+
+        // stake_amount already covered both accounts
+        ctx.accounts.resolution_account.second_stake_account = None;
+
+        emit!(StakeMerged {
+            resolution: resolution_key,
+            owner: ctx.accounts.owner.key(),
+            stake_account: ctx.accounts.stake_account.key(),
+            merged_stake_account: ctx.accounts.second_stake_account.key(),
+            lamports: merged_lamports,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let rent_exempt_reserve = stake_state(&ctx.accounts.stake_account)?
+            .meta()
+            .map(|meta| meta.rent_exempt_reserve)
+            .ok_or(ResolutionErrorCode::InvalidStakeAccount)?;
+
+        // Anything above rent + principal is rewards, principal is never touched
+        let rewards = accrued_rewards(
+            ctx.accounts.stake_account.lamports(),
+            rent_exempt_reserve,
+            ctx.accounts.resolution_account.stake_amount,
+        );
+
+        if rewards == 0 {
+            return Err(ResolutionErrorCode::NoRewardsToClaim.into());
+        }
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        let resolution_key = ctx.accounts.resolution_account.key();
+
+        msg!("Rewards: {}", rewards);
+
+        // Lockup is in force, so the custodian signs
+        let (custodian_key, custodian) =
+            lockup_custodian(&ctx.accounts.resolution_account, &ctx.accounts.custodian)?;
+        invoke_signed(
+            &withdraw(
+                &ctx.accounts.stake_account.key(),
+                &ctx.accounts.withdraw_destination.key(),
+                &ctx.accounts.withdraw_destination.key(),
+                rewards,
+                custodian_key.as_ref(),
+            ),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.withdraw_destination.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.withdraw_destination.to_account_info(),
+                custodian,
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(RewardsClaimed {
+            resolution: resolution_key,
+            owner: ctx.accounts.owner.key(),
+            amount: rewards,
+        });
+
+        Ok(())
+    }
+
+    // The resolution PDA is seeded by the owner, so transferring ownership moves the
+    // state to a new PDA under the new owner's seeds (same nonce) and closes the old one.
+    // The stake account follows: withdrawer becomes the new owner (unless a separate
+    // withdraw destination was set), while the staker and lockup custodian become the new PDA.
+    pub fn transfer_ownership(ctx: Context<TransferOwnership>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let new_owner = ctx.accounts.new_owner.key();
+        let resolution = &ctx.accounts.resolution_account;
+
+        // approvers must stay independent from whoever gets the stake back
+        if new_owner == resolution.owner || resolution.approvers.contains(&new_owner) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+
+        // the split off stake account would keep the old authorities
+        if resolution.second_stake_account.is_some() {
+            return Err(ResolutionErrorCode::StakeAlreadySplit.into());
+        }
+
+        // the details PDA is seeded by the old resolution, so it's closed rather than moved
+        ensure_details_passed(resolution, &ctx.accounts.details)?;
+
+        // a vault can't be closed like the details, it's what the approvers vouched for
+        if resolution.has_vault {
+            return Err(ResolutionErrorCode::VaultAttached.into());
+        }
+
+        // Past the deadline the resolution only needs closing, and the stake
+        // program would no longer accept the PDA as lockup custodian
+        if Clock::get()?.unix_timestamp >= resolution.end_time {
+            return Err(ResolutionErrorCode::ResolutionExpired.into());
+        }
+
+        let nonce_bytes = resolution.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        let resolution_key = ctx.accounts.resolution_account.key();
+        let new_resolution_key = ctx.accounts.new_resolution_account.key();
+
+        // A separately set withdraw destination isn't the owner's to hand over
+        let moves_withdrawer = resolution.withdraw_destination == ctx.accounts.owner.key();
+
+        // Lockup is in force, so the current custodian (old PDA unless one was named) signs every change
+        let (custodian_key, custodian) =
+            lockup_custodian(&ctx.accounts.resolution_account, &ctx.accounts.custodian)?;
+        let custodian_key = custodian_key.ok_or(ResolutionErrorCode::InvalidCustodian)?;
+
+        if moves_withdrawer {
+            invoke_signed(
+                &authorize(
+                    &ctx.accounts.stake_account.key(),
+                    &ctx.accounts.owner.key(),
+                    &new_owner,
+                    StakeAuthorize::Withdrawer,
+                    Some(&custodian_key),
+                ),
+                &[
+                    ctx.accounts.stake_account.to_account_info(),
+                    ctx.accounts.clock.to_account_info(),
+                    ctx.accounts.owner.to_account_info(),
+                    custodian.clone(),
+                ],
+                signer_seeds,
+            )?;
+        }
+
+        invoke_signed(
+            &authorize(
+                &ctx.accounts.stake_account.key(),
+                &resolution_key,
+                &new_resolution_key,
+                StakeAuthorize::Staker,
+                None,
+            ),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.resolution_account.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        // A named custodian isn't tied to the PDA, so it stays in place
+        if ctx.accounts.resolution_account.custodian.is_none() {
+            invoke_signed(
+                &set_lockup(
+                    &ctx.accounts.stake_account.key(),
+                    &LockupArgs {
+                        unix_timestamp: None,
+                        epoch: None,
+                        custodian: Some(new_resolution_key),
+                    },
+                    &custodian_key,
+                ),
+                &[ctx.accounts.stake_account.to_account_info(), custodian],
+                signer_seeds,
+            )?;
+        }
+
+        // The reward escrow moves along, closing the old PDA would hand it to the old owner
+        let reward_escrow = escrowed_lamports(&ctx.accounts.resolution_account.to_account_info())?;
+        if reward_escrow > 0 {
+            ctx.accounts
+                .resolution_account
+                .sub_lamports(reward_escrow)?;
+            ctx.accounts
+                .new_resolution_account
+                .add_lamports(reward_escrow)?;
+        }
+
+        let mut state = (*ctx.accounts.resolution_account).clone();
+        state.owner = new_owner;
+        if moves_withdrawer {
+            state.withdraw_destination = new_owner;
+        }
+        state.has_details = false;
+        state.bump = ctx.bumps.new_resolution_account;
+        ctx.accounts.new_resolution_account.set_inner(state);
+
+        emit!(OwnershipTransferred {
+            resolution: resolution_key,
+            new_resolution: new_resolution_key,
+            owner: ctx.accounts.owner.key(),
+            new_owner,
+        });
+
+        Ok(())
+    }
+
+    // The stake program still only releases lamports that aren't actively staked,
+    // so withdrawing delegated principal requires deactivating first
+    pub fn partial_withdraw(ctx: Context<PartialWithdraw>, amount: u64) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        if !ctx.accounts.resolution_account.is_approved(now) {
+            return Err(ResolutionErrorCode::NotEnoughApprovals.into());
+        }
+
+        withdraw_unlocked(ctx.accounts, ctx.bumps.resolution_account, amount)?;
+
+        emit!(PartialWithdrawal {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.resolution_account.owner,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    // Approvers sign off on the next milestone; release_milestone needs as many sign offs
+    // as the goal itself
+    pub fn approve_milestone(ctx: Context<ApproveResolution>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+        let signer = ctx.accounts.signer.key();
+
+        if resolution.next_milestone().is_none() {
+            return Err(ResolutionErrorCode::NoPendingMilestone.into());
+        }
+
+        if !resolution.approvers.contains(&signer) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+
+        if resolution.milestone_approved_by.contains(&signer) {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        resolution.milestone_approved_by.push(signer);
+
+        Ok(())
+    }
+
+    // Releases the next milestone's amount once its time has come and enough approvers
+    // signed off on it (or on the whole goal). Same stake program limits as partial_withdraw
+    pub fn release_milestone(ctx: Context<PartialWithdraw>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let resolution = &ctx.accounts.resolution_account;
+
+        let (index, release_time, amount) = resolution
+            .next_milestone()
+            .ok_or(ResolutionErrorCode::NoPendingMilestone)?;
+
+        if now < release_time {
+            return Err(ResolutionErrorCode::MilestoneNotReached.into());
+        }
+
+        if resolution.total_weight(&resolution.milestone_approved_by)
+            < resolution.required_approvals as u16
+            && !resolution.is_approved(now)
+        {
+            return Err(ResolutionErrorCode::NotEnoughApprovals.into());
+        }
+
+        withdraw_unlocked(ctx.accounts, ctx.bumps.resolution_account, amount)?;
+
+        let resolution = &mut ctx.accounts.resolution_account;
+        resolution.milestones_released += 1;
+        resolution.milestone_approved_by = [].to_vec();
+
+        emit!(MilestoneReleased {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            index,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn get_time_remaining(ctx: Context<ViewResolution>) -> Result<i64> {
+        let now = Clock::get()?.unix_timestamp;
+        let time_remaining = ctx
+            .accounts
+            .resolution_account
+            .end_time
+            .saturating_sub(now)
+            .max(0);
+
+        msg!("Time remaining: {}", time_remaining);
+
+        Ok(time_remaining)
+    }
+
+    // A closed resolution has no account left, so the view itself fails for it
+    pub fn get_status(ctx: Context<ViewResolution>) -> Result<ResolutionStatus> {
+        let now = Clock::get()?.unix_timestamp;
+        let status = ctx.accounts.resolution_account.status(now);
+
+        msg!("Status: {:?}", status);
+
+        Ok(status)
+    }
+
+    // Fixed layout summary for other programs, so they don't depend on the Anchor account
+    // layout. Logged with sol_log_data, which shows up base64 encoded as "Program data:",
+    // and returned as return data
+    pub fn get_resolution_summary(ctx: Context<ViewResolution>) -> Result<Vec<u8>> {
+        let now = Clock::get()?.unix_timestamp;
+        let summary = ResolutionSummary::new(&ctx.accounts.resolution_account, now).to_bytes();
+
+        sol_log_data(&[&summary]);
+
+        Ok(summary.to_vec())
+    }
+
+    // Approvers that still have to approve, in the order they were added
+    pub fn get_pending_approvers(ctx: Context<ViewResolution>) -> Result<Vec<Pubkey>> {
+        let pending_approvers = ctx.accounts.resolution_account.pending_approvers();
+
+        for approver in &pending_approvers {
+            msg!("Pending approver: {}", approver);
+        }
+
+        Ok(pending_approvers)
+    }
+
+    // Progress bar for frontends in one call. is_approved goes through the same
+    // approval_progress, so the bar and close_resolution can't disagree: stale approvals
+    // don't count, and weighted resolutions report weight instead of a count
+    pub fn get_approval_progress(ctx: Context<ViewResolution>) -> Result<ApprovalProgress> {
+        let now = Clock::get()?.unix_timestamp;
+        let progress = ctx.accounts.resolution_account.approval_progress(now);
+
+        msg!("Approvals: {}/{}", progress.approved, progress.required);
+        msg!("Threshold met: {}", progress.threshold_met);
+
+        Ok(progress)
+    }
+
+    // Runs the same checks as close_resolution without touching anything, so clients
+    // can tell why a close would fail before paying for it
+    pub fn can_close(ctx: Context<CanClose>) -> Result<CloseEligibility> {
+        let resolution = &ctx.accounts.resolution_account;
+        let stake_accounts = if resolution.is_stakeless() {
+            None
+        } else {
+            let second_stake_account =
+                second_stake_account(resolution, &ctx.accounts.second_stake_account)?;
+            Some(std::iter::once(&ctx.accounts.stake_account).chain(second_stake_account))
+        };
+
+        let eligibility = close_eligibility(
+            resolution,
+            ctx.accounts.clock.unix_timestamp,
+            stake_accounts.into_iter().flatten(),
+            &ctx.accounts.clock,
+            &ctx.accounts.stake_history,
+        )?;
+
+        msg!("Close eligibility: {:?}", eligibility);
+
+        Ok(eligibility)
+    }
+
+    // end_time is what this program enforces and the stake lockup is what the stake program
+    // enforces, so every instruction that moves one has to move the other. Returns the
+    // shared lockup end and fails with LockupMismatch if any stake account has drifted
+    pub fn verify_lockup_consistency(ctx: Context<VerifyLockupConsistency>) -> Result<i64> {
+        let resolution = &ctx.accounts.resolution_account;
+        let second_stake_account =
+            second_stake_account(resolution, &ctx.accounts.second_stake_account)?;
+
+        msg!("End time: {}", resolution.end_time);
+
+        for stake_account in
+            std::iter::once(&ctx.accounts.stake_account).chain(second_stake_account)
+        {
+            let lockup = stake_state(stake_account)?
+                .lockup()
+                .ok_or(ResolutionErrorCode::InvalidStakeAccount)?;
+
+            msg!(
+                "Stake {} lockup: {} (epoch {})",
+                stake_account.key(),
+                lockup.unix_timestamp,
+                lockup.epoch
+            );
+
+            ensure_lockup_matches(resolution.end_time, &lockup)?;
+        }
+
+        Ok(resolution.end_time)
+    }
+
+    // approved_by only ever grows by push and shrinks by remove, so it stays in the
+    // order approvers signed; approval_notes and approved_at line up with it
+    pub fn get_approval_order(ctx: Context<ViewResolution>) -> Result<Vec<Pubkey>> {
+        let approved_by = ctx.accounts.resolution_account.approved_by.clone();
+
+        for (position, approver) in approved_by.iter().enumerate() {
+            msg!("Approval {}: {}", position + 1, approver);
+        }
+
+        Ok(approved_by)
+    }
+
+    // Lets clients decide whether to offer target the approve action without fetching the account
+    pub fn is_approver(ctx: Context<ViewResolution>, target: Pubkey) -> Result<bool> {
+        let is_approver = ctx.accounts.resolution_account.approvers.contains(&target);
+
+        msg!("Is approver: {}", is_approver);
+
+        Ok(is_approver)
+    }
+
+    pub fn preview_withdrawable(ctx: Context<PreviewWithdrawable>) -> Result<WithdrawablePreview> {
+        let total_lamports = ctx.accounts.stake_account.lamports();
+        let rent_exempt_reserve = Rent::get()?.minimum_balance(StakeStateV2::size_of());
+
+        msg!("Total lamports: {}", total_lamports);
+        msg!("Rent exempt reserve: {}", rent_exempt_reserve);
+
+        Ok(WithdrawablePreview {
+            total_lamports,
+            rent_exempt_reserve,
+        })
+    }
+
+    // What claim_rewards would pay out right now, without moving anything
+    pub fn get_accrued_rewards(ctx: Context<PreviewWithdrawable>) -> Result<u64> {
+        let rent_exempt_reserve = stake_state(&ctx.accounts.stake_account)?
+            .meta()
+            .map(|meta| meta.rent_exempt_reserve)
+            .ok_or(ResolutionErrorCode::InvalidStakeAccount)?;
+
+        let rewards = accrued_rewards(
+            ctx.accounts.stake_account.lamports(),
+            rent_exempt_reserve,
+            ctx.accounts.resolution_account.stake_amount,
+        );
+
+        msg!("Accrued rewards: {}", rewards);
+
+        Ok(rewards)
+    }
+
+    // Canonical `["resolution", owner, nonce]` address for clients that would otherwise
+    // hand-roll the seeds. Reads no accounts, the resolution doesn't have to exist yet
+    pub fn derive_resolution_address(
+        _ctx: Context<DeriveResolutionAddress>,
+        owner: Pubkey,
+        nonce: u64,
+    ) -> Result<ResolutionAddress> {
+        let (address, bump) = Pubkey::find_program_address(
+            &[b"resolution", owner.as_ref(), &nonce.to_le_bytes()],
+            &crate::ID,
+        );
+
+        msg!("Resolution address: {}", address);
+        msg!("Bump: {}", bump);
+
+        Ok(ResolutionAddress { address, bump })
+    }
+
+    pub fn deactivate_resolution_stake(ctx: Context<DeactivateResolutionStake>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let second_stake_account = second_stake_account(
+            &ctx.accounts.resolution_account,
+            &ctx.accounts.second_stake_account,
+        )?;
+
+        // A second deactivate fails deep in the stake program, so catch it up front
+        for stake_account in
+            std::iter::once(&ctx.accounts.stake_account).chain(second_stake_account)
+        {
+            ensure_staked_by(stake_account, &ctx.accounts.resolution_account.key())?;
+
+            if let Some(delegation) = stake_state(stake_account)?.delegation() {
+                if delegation.deactivation_epoch != u64::MAX {
+                    return Err(ResolutionErrorCode::AlreadyDeactivating.into());
+                }
+            }
+        }
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        invoke_signed(
+            &deactivate_stake(
+                &ctx.accounts.stake_account.key(),
+                &ctx.accounts.resolution_account.key(),
+            ),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.resolution_account.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(StakeDeactivated {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            stake_account: ctx.accounts.stake_account.key(),
+        });
+
+        if let Some(second_stake_account) = second_stake_account {
+            invoke_signed(
+                &deactivate_stake(
+                    second_stake_account.key,
+                    &ctx.accounts.resolution_account.key(),
+                ),
+                &[
+                    second_stake_account.to_account_info(),
+                    ctx.accounts.clock.to_account_info(),
+                    ctx.accounts.resolution_account.to_account_info(),
+                ],
+                signer_seeds,
+            )?;
+
+            emit!(StakeDeactivated {
+                resolution: ctx.accounts.resolution_account.key(),
+                owner: ctx.accounts.owner.key(),
+                stake_account: second_stake_account.key(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // The stake program lets a deactivation be rescinded by delegating to the same vote
+    // account within the deactivation epoch. Only the main stake account is reactivated,
+    // the split off stake was delegated to a validator the resolution doesn't track.
+    pub fn reactivate_stake(ctx: Context<ReactivateStake>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let current_epoch = Clock::get()?.epoch;
+        let deactivation_epoch = stake_state(&ctx.accounts.stake_account)?
+            .delegation()
+            .map(|delegation| delegation.deactivation_epoch)
+            .ok_or(ResolutionErrorCode::InvalidStakeAccount)?;
+
+        if deactivation_epoch != current_epoch {
+            return Err(ResolutionErrorCode::StakeNotDeactivating.into());
+        }
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        invoke_signed(
+            &delegate_stake(
+                ctx.accounts.stake_account.key,
+                &ctx.accounts.resolution_account.key(),
+                ctx.accounts.validator_vote_account.key,
+            ),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.validator_vote_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.stake_config.to_account_info(),
+                ctx.accounts.resolution_account.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        // the stake is earning again, so a pending two-phase close has to start over
+        let resolution = &mut ctx.accounts.resolution_account;
+        resolution.close_requested_at = 0;
+        resolution.close_requested_epoch = 0;
+
+        emit!(StakeReactivated {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            stake_account: ctx.accounts.stake_account.key(),
+        });
+
+        Ok(())
+    }
+
+    // First half of the two-phase close: deactivates the stake and records the epoch it
+    // happened in, since the stake can only be withdrawn once that epoch is over
+    pub fn initiate_close(ctx: Context<DeactivateResolutionStake>) -> Result<()> {
+        let clock = Clock::get()?;
+        let resolution = &mut ctx.accounts.resolution_account;
+
+        if resolution.close_requested_at != 0 {
+            return Err(ResolutionErrorCode::CloseAlreadyRequested.into());
+        }
+
+        resolution.close_requested_at = clock.unix_timestamp;
+        resolution.close_requested_epoch = clock.epoch;
+
+        emit!(CloseInitiated {
+            resolution: resolution.key(),
+            owner: resolution.owner,
+            requested_at: clock.unix_timestamp,
+            deactivation_epoch: clock.epoch,
+        });
+
+        deactivate_resolution_stake(ctx)
+    }
+
+    // Second half of the two-phase close: pays out and closes exactly like close_resolution,
+    // but only after initiate_close and once its deactivation epoch has ended
+    pub fn finalize_close(ctx: Context<CloseResolution>) -> Result<()> {
+        let resolution = &ctx.accounts.resolution_account;
+
+        if resolution.close_requested_at == 0 {
+            return Err(ResolutionErrorCode::CloseNotRequested.into());
+        }
+
+        if Clock::get()?.epoch <= resolution.close_requested_epoch {
+            msg!(
+                "Stake is deactivating until the end of epoch {}",
+                resolution.close_requested_epoch
+            );
+            return Err(ResolutionErrorCode::DeactivationEpochNotReached.into());
+        }
+
+        close_resolution(ctx)
+    }
+
+    pub fn cancel_resolution(ctx: Context<CancelResolution>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &ctx.accounts.resolution_account;
+
+        // Once someone has vouched, the owner can't back out
+        if !resolution.approved_by.is_empty() {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        // cancelling would leave the split off stake behind
+        if resolution.second_stake_account.is_some() {
+            return Err(ResolutionErrorCode::StakeAlreadySplit.into());
+        }
+
+        ensure_details_passed(resolution, &ctx.accounts.details)?;
+        ensure_vault_passed(resolution, &ctx.accounts.vault)?;
+
+        let current_epoch = Clock::get()?.epoch;
+        let activation_epoch = stake_state(&ctx.accounts.stake_account)?
+            .delegation()
+            .map(|delegation| delegation.activation_epoch)
+            .ok_or(ResolutionErrorCode::InvalidStakeAccount)?;
+
+        if current_epoch != activation_epoch {
+            return Err(ResolutionErrorCode::CancelWindowClosed.into());
+        }
+
+        let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        invoke_signed(
+            &deactivate_stake(
+                &ctx.accounts.stake_account.key(),
+                &ctx.accounts.resolution_account.key(),
+            ),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.resolution_account.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        // Stake deactivated in its activation epoch never becomes effective,
+        // so the whole balance is withdrawable with the custodian signature
+        let withdraw_amount = ctx.accounts.stake_account.lamports();
+        let resolution_key = ctx.accounts.resolution_account.key();
+        let (custodian_key, custodian) =
+            lockup_custodian(&ctx.accounts.resolution_account, &ctx.accounts.custodian)?;
+
+        invoke_signed(
+            &withdraw(
+                &ctx.accounts.stake_account.key(),
+                &ctx.accounts.withdraw_destination.key(),
+                &ctx.accounts.withdraw_destination.key(),
+                withdraw_amount,
+                custodian_key.as_ref(),
+            ),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.withdraw_destination.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.withdraw_destination.to_account_info(),
+                custodian,
+            ],
+            signer_seeds,
+        )?;
+
+        ctx.accounts
+            .stats
+            .record_closed(ctx.accounts.resolution_account.stake_amount);
+
+        emit!(ResolutionCancelled {
+            resolution: resolution_key,
+            owner: ctx.accounts.owner.key(),
+            withdraw_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_resolution(ctx: Context<CloseResolution>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        ensure_no_deactivate_in_transaction(
+            &ctx.accounts.instructions_sysvar,
+            &ctx.accounts.resolution_account.key(),
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        let resolution_key = ctx.accounts.resolution_account.key();
+        let resolution = &ctx.accounts.resolution_account;
+
+        // an unanimous emergency release pays out like an approved resolution
+        let is_approved = resolution.is_approved(now) || resolution.is_emergency_released();
+
+        ensure_details_passed(resolution, &ctx.accounts.details)?;
+        ensure_vault_passed(resolution, &ctx.accounts.vault)?;
+
+        let second_stake_account =
+            second_stake_account(resolution, &ctx.accounts.second_stake_account)?;
+
+        for stake_account in
+            std::iter::once(&ctx.accounts.stake_account).chain(second_stake_account)
+        {
+            ensure_staked_by(stake_account, &resolution_key)?;
+        }
+
+        close_eligibility(
+            resolution,
+            now,
+            std::iter::once(&ctx.accounts.stake_account).chain(second_stake_account),
+            &ctx.accounts.clock,
+            &ctx.accounts.stake_history,
+        )?
+        .ensure()?;
+
+        let nonce_bytes = resolution.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        let (custodian_key, custodian) =
+            lockup_custodian(&ctx.accounts.resolution_account, &ctx.accounts.custodian)?;
+
+        // Fold the split off stake back into the main stake account so the
+        // payout below works on a single balance
+        if let Some(second_stake_account) = second_stake_account {
+            invoke_signed(
+                &withdraw(
+                    second_stake_account.key,
+                    &ctx.accounts.withdraw_destination.key(),
+                    ctx.accounts.stake_account.key,
+                    second_stake_account.lamports(),
+                    custodian_key.as_ref(),
+                ),
+                &[
+                    second_stake_account.to_account_info(),
+                    ctx.accounts.stake_account.to_account_info(),
+                    ctx.accounts.clock.to_account_info(),
+                    ctx.accounts.stake_history.to_account_info(),
+                    ctx.accounts.withdraw_destination.to_account_info(),
+                    custodian.clone(),
+                ],
+                signer_seeds,
+            )?;
+        }
+
+        // Calculate withdraw amount and burn amount
+        // If approved, withdraw all stake account balance
+        // If not approved, withdraw the stake amount and burn the rest (rewards+rent)
+        // The stake account is abandoned either way, so even its rent reserve goes out
+        let stake_lamports = ctx.accounts.stake_account.lamports();
+        let (withdraw_amount, burn_amount) = if is_approved {
+            (stake_lamports, 0)
+        } else {
+            // a balance below the principal (e.g. after slashing) is returned as is
+            let withdraw_amount = resolution.stake_amount.min(stake_lamports);
+            (withdraw_amount, stake_lamports - withdraw_amount)
+        };
+
+        // If not approved, penalty_bps of the returned stake goes to the charity
+        let penalty_amount = if is_approved {
+            0
+        } else {
+            (withdraw_amount as u128 * resolution.penalty_bps as u128 / BPS_DENOMINATOR as u128)
+                as u64
+        };
+        let withdraw_amount = withdraw_amount - penalty_amount;
+
+        msg!("Withdraw amount: {}", withdraw_amount);
+
+        // The withdraw authority signs, the recipient only receives the stake.
+        // An approved payout goes into the vault when there is one
+        let recipient = match (&ctx.accounts.vault, &ctx.accounts.recipient) {
+            (Some(vault), _) if is_approved => vault.to_account_info(),
+            (_, Some(recipient)) => recipient.to_account_info(),
+            (_, None) => ctx.accounts.withdraw_destination.to_account_info(),
+        };
+        ensure_not_approver(&resolution.approvers, recipient.key)?;
+
+        match is_approved {
+            true => {
+                invoke_signed(
+                    &withdraw(
+                        &ctx.accounts.stake_account.key(),
+                        &ctx.accounts.withdraw_destination.key(),
+                        recipient.key,
+                        withdraw_amount,
+                        custodian_key.as_ref(),
+                    ),
+                    &[
+                        ctx.accounts.stake_account.to_account_info(),
+                        recipient.clone(),
+                        ctx.accounts.clock.to_account_info(),
+                        ctx.accounts.stake_history.to_account_info(),
+                        ctx.accounts.withdraw_destination.to_account_info(),
+                        custodian.clone(),
+                    ],
+                    signer_seeds,
+                )?;
+            }
+            false => {
+                invoke(
+                    &withdraw(
+                        &ctx.accounts.stake_account.key(),
+                        &ctx.accounts.withdraw_destination.key(),
+                        recipient.key,
+                        withdraw_amount,
+                        None,
+                    ),
+                    &[
+                        ctx.accounts.stake_account.to_account_info(),
+                        recipient.clone(),
+                        ctx.accounts.clock.to_account_info(),
+                        ctx.accounts.stake_history.to_account_info(),
+                        ctx.accounts.withdraw_destination.to_account_info(),
+                    ],
+                )?;
+
+                msg!("Penalty amount: {}", penalty_amount);
+
+                if penalty_amount > 0 {
+                    invoke(
+                        &withdraw(
+                            &ctx.accounts.stake_account.key(),
+                            &ctx.accounts.withdraw_destination.key(),
+                            &ctx.accounts.charity.key(),
+                            penalty_amount,
+                            None,
+                        ),
+                        &[
+                            ctx.accounts.stake_account.to_account_info(),
+                            ctx.accounts.charity.to_account_info(),
+                            ctx.accounts.clock.to_account_info(),
+                            ctx.accounts.stake_history.to_account_info(),
+                            ctx.accounts.withdraw_destination.to_account_info(),
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        msg!("Burn amount: {}", burn_amount);
+
+        if burn_amount > 0 {
+            invoke_signed(
+                &withdraw(
+                    &ctx.accounts.stake_account.key(),
+                    &ctx.accounts.withdraw_destination.key(),
+                    &ctx.accounts.incinerator_account.key(),
+                    burn_amount,
+                    custodian_key.as_ref(),
+                ),
+                &[
+                    ctx.accounts.stake_account.to_account_info(),
+                    ctx.accounts.incinerator_account.to_account_info(),
+                    ctx.accounts.clock.to_account_info(),
+                    ctx.accounts.stake_history.to_account_info(),
+                    ctx.accounts.withdraw_destination.to_account_info(),
+                    custodian.clone(),
+                ],
+                signer_seeds,
+            )?;
+        }
+
+        // Every lamport should be accounted for above, anything left would be stranded
+        let remaining = ctx.accounts.stake_account.lamports();
+        if remaining > 0 {
+            msg!("Stake account still holds {} lamports", remaining);
+            return Err(ResolutionErrorCode::StakeNotDrained.into());
+        }
+
+        // The stream starts now and goes to whoever the payout would have gone to.
+        // Without approval nothing is streamed and the empty vault closes with the resolution
+        if let Some(vault) = ctx.accounts.vault.as_mut() {
+            if is_approved {
+                vault.beneficiary = match &ctx.accounts.recipient {
+                    Some(recipient) => recipient.key(),
+                    None => ctx.accounts.withdraw_destination.key(),
+                };
+                vault.total_amount = withdraw_amount;
+                vault.start_time = now;
+            } else {
+                vault.close(ctx.accounts.owner.to_account_info())?;
+            }
+        }
+
+        // Without approval the whole escrow goes back to the owner when the PDA closes
+        let approver_rewards = if is_approved {
+            pay_approver_rewards(
+                &ctx.accounts.resolution_account.to_account_info(),
+                ctx.accounts.resolution_account.approver_reward,
+                &ctx.accounts.resolution_account.approved_by,
+                ctx.remaining_accounts,
+            )?
+        } else {
+            0
+        };
+
+        // stake_amount is the locked principal however the payout was split up
+        ctx.accounts
+            .stats
+            .record_closed(ctx.accounts.resolution_account.stake_amount);
+
+        emit!(ResolutionClosed {
+            resolution: resolution_key,
+            owner: ctx.accounts.owner.key(),
+            recipient: recipient.key(),
+            was_approved: is_approved,
+            withdraw_amount,
+            penalty_amount,
+            burn_amount,
+            approver_rewards,
+        });
+
+        Ok(())
+    }
+
+    // Stakeless resolutions have no stake account to settle, so they close on
+    // their own without the stake accounts and sysvars close_resolution needs
+    pub fn close_stakeless_resolution(ctx: Context<CloseStakelessResolution>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let clock = Clock::get()?;
+
+        ensure_details_passed(&ctx.accounts.resolution_account, &ctx.accounts.details)?;
+
+        close_eligibility(
+            &ctx.accounts.resolution_account,
+            clock.unix_timestamp,
+            std::iter::empty::<&AccountInfo>(),
+            &clock,
+            &StakeHistory::default(),
+        )?
+        .ensure()?;
+
+        let resolution = &ctx.accounts.resolution_account;
+        let is_approved =
+            resolution.is_approved(clock.unix_timestamp) || resolution.is_emergency_released();
+        let approver_rewards = if is_approved {
+            pay_approver_rewards(
+                &resolution.to_account_info(),
+                resolution.approver_reward,
+                &resolution.approved_by,
+                ctx.remaining_accounts,
+            )?
+        } else {
+            0
+        };
+
+        ctx.accounts.stats.record_closed(0);
+
+        emit!(ResolutionClosed {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            recipient: ctx.accounts.owner.key(),
+            was_approved: is_approved,
+            withdraw_amount: 0,
+            penalty_amount: 0,
+            burn_amount: 0,
+            approver_rewards,
+        });
+
+        Ok(())
+    }
+
+    // For a stake account that was emptied out-of-band, e.g. by a withdraw authority acting
+    // directly once the lockup ended. There's no stake left to pay out or penalize, so the
+    // resolution's rent, including any escrowed approver reward, goes back to the owner
+    // without any stake CPIs
+    pub fn close_orphaned_resolution(ctx: Context<CloseOrphanedResolution>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &ctx.accounts.resolution_account;
+
+        ensure_details_passed(resolution, &ctx.accounts.details)?;
+        ensure_vault_passed(resolution, &ctx.accounts.vault)?;
+
+        if let Some(second_stake_account) =
+            second_stake_account(resolution, &ctx.accounts.second_stake_account)?
+        {
+            if !is_orphaned(second_stake_account) {
+                return Err(ResolutionErrorCode::StakeAccountNotClosed.into());
+            }
+        }
+
+        ctx.accounts.stats.record_closed(resolution.stake_amount);
+
+        emit!(OrphanedResolutionClosed {
+            resolution: resolution.key(),
+            owner: ctx.accounts.owner.key(),
+            stake_account: ctx.accounts.stake_account.key(),
+        });
+
+        Ok(())
+    }
+
+    // Pooled resolutions hold pool tokens rather than a stake account, so they close through
+    // the stake pool program instead of close_resolution. The pool pays the SOL out to the
+    // resolution PDA, which then splits it up the same way close_resolution does
+    pub fn close_pool_resolution(ctx: Context<ClosePoolResolution>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let clock = Clock::get()?;
+
+        let resolution_key = ctx.accounts.resolution_account.key();
+        let resolution = &ctx.accounts.resolution_account;
+        let is_approved =
+            resolution.is_approved(clock.unix_timestamp) || resolution.is_emergency_released();
+
+        ensure_details_passed(resolution, &ctx.accounts.details)?;
+
+        close_eligibility(
+            resolution,
+            clock.unix_timestamp,
+            std::iter::empty::<&AccountInfo>(),
+            &clock,
+            &ctx.accounts.stake_history,
+        )?
+        .ensure()?;
+
+        let nonce_bytes = resolution.nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        // Withdrawing SOL needs enough liquidity in the pool reserve, otherwise the pool
+        // program fails and the close has to be retried once the reserve is refilled
+        let pool_tokens = ctx.accounts.pool_token_account.amount;
+        let lamports_before = ctx.accounts.resolution_account.get_lamports();
+        invoke_signed(
+            &stake_pool::withdraw_sol(
+                &ctx.accounts.stake_pool.key(),
+                &ctx.accounts.pool_withdraw_authority.key(),
+                &resolution_key,
+                &ctx.accounts.pool_token_account.key(),
+                &ctx.accounts.reserve_stake.key(),
+                &resolution_key,
+                &ctx.accounts.manager_fee_account.key(),
+                &ctx.accounts.pool_mint.key(),
+                &ctx.accounts.token_program.key(),
+                pool_tokens,
+            ),
+            &[
+                ctx.accounts.stake_pool.to_account_info(),
+                ctx.accounts.pool_withdraw_authority.to_account_info(),
+                ctx.accounts.resolution_account.to_account_info(),
+                ctx.accounts.pool_token_account.to_account_info(),
+                ctx.accounts.reserve_stake.to_account_info(),
+                ctx.accounts.manager_fee_account.to_account_info(),
+                ctx.accounts.pool_mint.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.stake_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+        let withdrawn_lamports = ctx
+            .accounts
+            .resolution_account
+            .get_lamports()
+            .saturating_sub(lamports_before);
+
+        // The emptied token account's rent goes back to the owner
+        close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.pool_token_account.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.resolution_account.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        // Same split as close_resolution, with the pool's yield in place of staking rewards
+        let resolution = &ctx.accounts.resolution_account;
+        let (withdraw_amount, burn_amount) = if is_approved {
+            (withdrawn_lamports, 0)
+        } else {
+            let withdraw_amount = resolution.stake_amount.min(withdrawn_lamports);
+            (withdraw_amount, withdrawn_lamports - withdraw_amount)
+        };
+        let penalty_amount = if is_approved {
+            0
+        } else {
+            (withdraw_amount as u128 * resolution.penalty_bps as u128 / BPS_DENOMINATOR as u128)
+                as u64
+        };
+        let withdraw_amount = withdraw_amount - penalty_amount;
+
+        msg!("Withdraw amount: {}", withdraw_amount);
+        msg!("Penalty amount: {}", penalty_amount);
+        msg!("Burn amount: {}", burn_amount);
+
+        // Everything the pool paid out leaves the PDA here, or close = owner would hand it
+        // to the owner along with the rent
+        ctx.accounts
+            .resolution_account
+            .sub_lamports(withdrawn_lamports)?;
+        ctx.accounts
+            .withdraw_destination
+            .add_lamports(withdraw_amount)?;
+        ctx.accounts.charity.add_lamports(penalty_amount)?;
+        ctx.accounts.incinerator_account.add_lamports(burn_amount)?;
+
+        let approver_rewards = if is_approved {
+            pay_approver_rewards(
+                &ctx.accounts.resolution_account.to_account_info(),
+                ctx.accounts.resolution_account.approver_reward,
+                &ctx.accounts.resolution_account.approved_by,
+                ctx.remaining_accounts,
+            )?
+        } else {
+            0
+        };
+
+        ctx.accounts
+            .stats
+            .record_closed(ctx.accounts.resolution_account.stake_amount);
+
+        emit!(ResolutionClosed {
+            resolution: resolution_key,
+            owner: ctx.accounts.owner.key(),
+            recipient: ctx.accounts.withdraw_destination.key(),
+            was_approved: is_approved,
+            withdraw_amount,
+            penalty_amount,
+            burn_amount,
+            approver_rewards,
+        });
+
+        Ok(())
+    }
+
+    // Deactivated stake stays effective until the next epoch and the stake program won't
+    // release effective stake even to the lockup custodian, so this only gets through
+    // in the stake's activation epoch or once a previous deactivation has cooled down
+    pub fn deactivate_and_close(ctx: Context<CloseResolution>) -> Result<()> {
+        ctx.accounts
+            .resolution_account
+            .ensure_bump(ctx.bumps.resolution_account)?;
+
+        let resolution = &ctx.accounts.resolution_account;
+        if !resolution.is_approved(Clock::get()?.unix_timestamp)
+            && !resolution.is_emergency_released()
+        {
+            msg!("Call deactivate_resolution_stake and close_resolution once the cooldown epoch has passed");
+            return Err(ResolutionErrorCode::DeactivationPending.into());
+        }
+
+        {
+            let second_stake_account = second_stake_account(
+                &ctx.accounts.resolution_account,
+                &ctx.accounts.second_stake_account,
+            )?;
+
+            let nonce_bytes = ctx.accounts.resolution_account.nonce.to_le_bytes();
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"resolution",
+                ctx.accounts.owner.key.as_ref(),
+                &nonce_bytes,
+                &[ctx.bumps.resolution_account],
+            ]];
+
+            // an earlier deactivate_resolution_stake is fine here, only deactivate what's still delegated
+            for stake_account in
+                std::iter::once(&ctx.accounts.stake_account).chain(second_stake_account)
+            {
+                ensure_staked_by(stake_account, &ctx.accounts.resolution_account.key())?;
+
+                let needs_deactivation = stake_state(stake_account)?
+                    .delegation()
+                    .is_some_and(|delegation| delegation.deactivation_epoch == u64::MAX);
+
+                if !needs_deactivation {
+                    continue;
+                }
+
+                invoke_signed(
+                    &deactivate_stake(stake_account.key, &ctx.accounts.resolution_account.key()),
+                    &[
+                        stake_account.to_account_info(),
+                        ctx.accounts.clock.to_account_info(),
+                        ctx.accounts.resolution_account.to_account_info(),
+                    ],
+                    signer_seeds,
+                )?;
+
+                emit!(StakeDeactivated {
+                    resolution: ctx.accounts.resolution_account.key(),
+                    owner: ctx.accounts.owner.key(),
+                    stake_account: stake_account.key(),
+                });
+            }
+        }
+
+        // fails with StakeNotDeactivated if the stake is still effective this epoch
+        close_resolution(ctx)
+    }
+
+    // Lamports sent to a closed resolution's address would otherwise be stuck,
+    // since only the program can sign for the PDA
+    pub fn sweep_dust(ctx: Context<SweepDust>, nonce: u64) -> Result<()> {
+        let amount = ctx.accounts.resolution_account.lamports();
+
+        msg!("Dust amount: {}", amount);
+
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let nonce_bytes = nonce.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"resolution",
+            ctx.accounts.owner.key.as_ref(),
+            &nonce_bytes,
+            &[ctx.bumps.resolution_account],
+        ]];
+
+        invoke_signed(
+            &transfer(
+                &ctx.accounts.resolution_account.key(),
+                &ctx.accounts.owner.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.resolution_account.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        emit!(DustSwept {
+            resolution: ctx.accounts.resolution_account.key(),
+            owner: ctx.accounts.owner.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        payer = authority,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        space = 8 + Registry::INIT_SPACE,
+        payer = authority,
+        seeds = [b"registry"],
+        bump
+    )]
+    pub registry: Account<'info, Registry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRegistry<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, Registry>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeApproverPrograms<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        space = 8 + ApproverPrograms::INIT_SPACE,
+        payer = authority,
+        seeds = [b"approver_programs"],
+        bump
+    )]
+    pub approver_programs: Account<'info, ApproverPrograms>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateApproverPrograms<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"approver_programs"],
+        bump = approver_programs.bump
+    )]
+    pub approver_programs: Account<'info, ApproverPrograms>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        space = 8 + Stats::INIT_SPACE,
+        payer = payer,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct InitializeResolution<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        space = 8 + ResolutionAccount::INIT_SPACE,
+        payer = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ResolutionErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        init_if_needed,
+        space = 8 + OwnerProfile::INIT_SPACE,
+        payer = owner,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, OwnerProfile>,
+
+    /// Only needed when require_registered_approvers is set
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Option<Account<'info, Registry>>,
+
+    /// CHECK: We create the stake account in the instruction, so it has to be untouched beforehand.
+    /// Left out for stakeless resolutions
+    #[account(
+        mut,
+        constraint = stake_account.lamports() == 0 && stake_account.data_is_empty() @ ResolutionErrorCode::StakeAccountAlreadyInitialized
+    )]
+    pub stake_account: Option<Signer<'info>>,
+
+    /// CHECK: Alternative to stake_account that needs no extra signer, the program creates the
+    /// stake account at `["stake", resolution]`. Seeds are checked in create_resolution
+    #[account(
+        mut,
+        constraint = stake_pda.lamports() == 0 && stake_pda.data_is_empty() @ ResolutionErrorCode::StakeAccountAlreadyInitialized
+    )]
+    pub stake_pda: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The delegate instruction should fail if not a valid Vote account
+    #[account(
+        constraint = validator_vote_account.owner == &vote::program::ID @ ResolutionErrorCode::InvalidVoteAccount
+    )]
+    pub validator_vote_account: AccountInfo<'info>,
+
+    /// CHECK: We validate the stake config account
+    #[account(
+        constraint = stake_config.key() == pubkey!("StakeConfig11111111111111111111111111111111").key()
+    )]
+    pub stake_config: AccountInfo<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct InitializeResolutionV2<'info> {
+    pub base: InitializeResolution<'info>,
+
+    /// CHECK: Only the key is stored; approvers sign later in approve_resolution
+    pub approver_a: UncheckedAccount<'info>,
+
+    /// CHECK: Only the key is stored; approvers sign later in approve_resolution
+    pub approver_b: UncheckedAccount<'info>,
+
+    /// CHECK: Only the key is stored; approvers sign later in approve_resolution
+    pub approver_c: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct InitializeResolutionRandom<'info> {
+    pub base: InitializeResolution<'info>,
+
+    /// The approver registry, which approvers are drawn from
+    #[account(
+        seeds = [b"registry"],
+        bump = approver_pool.bump
+    )]
+    pub approver_pool: Account<'info, Registry>,
+
+    /// CHECK: We validate that the account key is the slot hashes sysvar. It's read raw
+    /// since the whole sysvar is too large to deserialize
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct RenewResolution<'info> {
+    pub base: InitializeResolution<'info>,
+
+    /// Seeded by the owner, so only the owner's own resolutions can be renewed
+    #[account(
+        seeds = [b"resolution", base.owner.key().as_ref(), &previous_resolution.nonce.to_le_bytes()],
+        bump
+    )]
+    pub previous_resolution: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct MigrateResolution<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Laid out as a LegacyResolutionAccount, which is decoded and checked in the
+    /// handler. The seeds are the first release's, without a nonce
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"resolution", owner.key().as_ref()],
+        bump
+    )]
+    pub legacy_resolution: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        space = 8 + ResolutionAccount::INIT_SPACE,
+        payer = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// CHECK: Has to be the legacy resolution's stake account, checked in the handler
+    #[account(
+        mut,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct InitializeResolutionPool<'info> {
+    pub base: InitializeResolution<'info>,
+
+    /// CHECK: Validated by the stake pool program, we only check it's one of its accounts
+    #[account(
+        mut,
+        owner = stake_pool::ID @ ResolutionErrorCode::InvalidStakePool
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's withdraw authority PDA, validated by the stake pool program
+    pub pool_withdraw_authority: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's reserve stake account, validated by the stake pool program
+    #[account(mut)]
+    pub reserve_stake: UncheckedAccount<'info>,
+
+    /// Receives the pool tokens. Has to be owned by the resolution PDA so only this
+    /// program can redeem them, e.g. its associated token account created beforehand
+    #[account(
+        mut,
+        token::mint = pool_mint,
+        token::authority = base.resolution_account
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: The pool manager's fee account, validated by the stake pool program
+    #[account(mut)]
+    pub manager_fee_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pool_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: We validate the program ID
+    #[account(
+        executable,
+        constraint = stake_pool_program.key() == stake_pool::ID @ ResolutionErrorCode::InvalidStakePoolProgram
+    )]
+    pub stake_pool_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveResolution<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct BatchApprove<'info> {
+    pub signer: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveWithSignature<'info> {
+    /// Submits the approver's signature and pays the fee, usually the owner
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: We validate that the account key is the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveViaCpi<'info> {
+    /// The approver PDA, signed for by its program through invoke_signed
+    pub approver: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        seeds = [b"approver_programs"],
+        bump = approver_programs.bump
+    )]
+    pub approver_programs: Account<'info, ApproverPrograms>,
+
+    /// CHECK: We validate that the account key is the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveEmergencyRelease<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeApproval<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReplaceApprover<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+    /// Only needed when the resolution requires registered approvers
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Option<Account<'info, Registry>>,
+}
+
+#[derive(Accounts)]
+pub struct AddApprover<'info> {
+    /// Pays the new approver's reward into the escrow
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+    /// Only needed when the resolution requires registered approvers
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Option<Account<'info, Registry>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveApprover<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateText<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct AttachDetails<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        init,
+        space = 8 + ResolutionDetails::INIT_SPACE,
+        payer = owner,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump
+    )]
+    pub details: Account<'info, ResolutionDetails>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AppendDetails<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump = details.bump
+    )]
+    pub details: Account<'info, ResolutionDetails>,
+}
+
+#[derive(Accounts)]
+pub struct OpenVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        init,
+        space = 8 + Vault::INIT_SPACE,
+        payer = owner,
+        seeds = [b"vault", resolution_account.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVault<'info> {
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    /// Gets the vault's rent back after the last claim
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = beneficiary,
+        has_one = owner,
+        seeds = [b"vault", vault.resolution.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCharity<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendLockup<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    /// The resolution PDA is the lockup custodian and signs the set_lockup CPI
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Lockup custodian named at init, if any. Has to sign whenever the lockup is still in force
+    pub custodian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveExtension<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    /// The resolution PDA is the lockup custodian and signs the set_lockup CPI
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Lockup custodian named at init, if any. Has to sign whenever the lockup is still in force
+    pub custodian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelExtension<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct AddStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedelegateStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: The delegate instruction should fail if not a valid Vote account
+    #[account(
+        constraint = validator_vote_account.owner == &vote::program::ID @ ResolutionErrorCode::InvalidVoteAccount
+    )]
+    pub validator_vote_account: AccountInfo<'info>,
+
+    /// CHECK: We validate the stake config account
+    #[account(
+        constraint = stake_config.key() == pubkey!("StakeConfig11111111111111111111111111111111").key()
+    )]
+    pub stake_config: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SplitStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    /// CHECK: Allocated and assigned to the stake program during the split, so it has to be a fresh keypair
+    #[account(mut)]
+    pub split_stake_account: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: The delegate instruction should fail if not a valid Vote account
+    #[account(
+        constraint = validator_vote_account.owner == &vote::program::ID @ ResolutionErrorCode::InvalidVoteAccount
+    )]
+    pub validator_vote_account: AccountInfo<'info>,
+
+    /// CHECK: We validate the stake config account
+    #[account(
+        constraint = stake_config.key() == pubkey!("StakeConfig11111111111111111111111111111111").key()
+    )]
+    pub stake_config: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MergeStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    /// CHECK: Has to be the resolution's second stake account, merged into stake_account
+    #[account(
+        mut,
+        constraint = resolution_account.second_stake_account == Some(second_stake_account.key()) @ ResolutionErrorCode::InvalidStakeAccount,
+        constraint = second_stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub second_stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        has_one = owner,
+        has_one = stake_account,
+        has_one = withdraw_destination,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// Withdraw authority of the stake account, the owner unless another wallet was set at init
+    #[account(mut)]
+    pub withdraw_destination: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Lockup custodian named at init, if any. Has to sign whenever the lockup is still in force
+    pub custodian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct TransferOwnership<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub new_owner: SystemAccount<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        init,
+        space = 8 + ResolutionAccount::INIT_SPACE,
+        payer = owner,
+        seeds = [b"resolution", new_owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub new_resolution_account: Account<'info, ResolutionAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Lockup custodian named at init, if any. Has to sign whenever the lockup is still in force
+    pub custodian: Option<Signer<'info>>,
+
+    /// Required once attach_details has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump = details.bump
+    )]
+    pub details: Option<Account<'info, ResolutionDetails>>,
+}
+
+#[derive(Accounts)]
+pub struct PartialWithdraw<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        has_one = withdraw_destination,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// Withdraw authority of the stake account, the owner unless another wallet was set at init
+    #[account(mut)]
+    pub withdraw_destination: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Lockup custodian named at init, if any. Has to sign whenever the lockup is still in force
+    pub custodian: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ViewResolution<'info> {
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        has_one = owner,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct DeriveResolutionAddress {}
+
+#[derive(Accounts)]
+pub struct PreviewWithdrawable<'info> {
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct CanClose<'info> {
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account. Stakeless
+    /// resolutions pass the default pubkey
+    #[account(
+        constraint = stake_account.owner == &stake::program::ID || stake_account.key() == Pubkey::default() @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: Required once split_stake has run, matched against the resolution in the instruction
+    pub second_stake_account: Option<AccountInfo<'info>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyLockupConsistency<'info> {
+    #[account()]
+    pub owner: SystemAccount<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: Required once split_stake has run, matched against the resolution in the instruction
+    pub second_stake_account: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct DeactivateResolutionStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account. One withdrawn
+    /// out-of-band is system-owned and has to go through close_orphaned_resolution
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner != &anchor_lang::system_program::ID @ ResolutionErrorCode::StakeAccountClosed,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: Required once split_stake has run, matched against the resolution in the instruction
+    #[account(mut)]
+    pub second_stake_account: Option<AccountInfo<'info>>,
+
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReactivateStake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = stake_account,
+        has_one = validator_vote_account,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: We validate using has_one, it has to be the vote account the stake was delegated to
+    pub validator_vote_account: AccountInfo<'info>,
+
+    /// CHECK: We validate the stake config account
+    #[account(
+        constraint = stake_config.key() == pubkey!("StakeConfig11111111111111111111111111111111").key()
+    )]
+    pub stake_config: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelResolution<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account.
+    /// Cancelling is only allowed in the stake's activation epoch, when deactivating
+    /// leaves no effective stake and an abandoned goal can be unwound without waiting out the lockup
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = stake_account,
+        has_one = withdraw_destination,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// Withdraw authority of the stake account, the owner unless another wallet was set at init
+    #[account(mut)]
+    pub withdraw_destination: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Lockup custodian named at init, if any. Has to sign whenever the lockup is still in force
+    pub custodian: Option<Signer<'info>>,
+
+    /// Required once attach_details has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump = details.bump
+    )]
+    pub details: Option<Account<'info, ResolutionDetails>>,
+
+    /// Required once open_vault has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"vault", resolution_account.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Option<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseResolution<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: We validate using has_one and the owner of the account. One withdrawn
+    /// out-of-band is system-owned and has to go through close_orphaned_resolution
+    #[account(
+        mut,
+        constraint = stake_account.key() != Pubkey::default() @ ResolutionErrorCode::NoStakeAttached,
+        constraint = stake_account.owner != &anchor_lang::system_program::ID @ ResolutionErrorCode::StakeAccountClosed,
+        constraint = stake_account.owner == &stake::program::ID @ ResolutionErrorCode::InvalidStakeAccount
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = stake_account,
+        has_one = withdraw_destination,
+        has_one = charity,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// Withdraw authority of the stake account, the owner unless another wallet was set at init
+    #[account(mut)]
+    pub withdraw_destination: Signer<'info>,
+
+    /// CHECK: We validate using has_one, it only receives the penalty lamports
+    #[account(mut)]
+    pub charity: AccountInfo<'info>,
+
+    /// Receives the withdrawn stake instead of the withdraw destination when given.
+    /// The owner and the withdraw destination still have to sign the close
+    #[account(mut)]
+    pub recipient: Option<SystemAccount<'info>>,
+
+    /// CHECK: Required once split_stake has run, matched against the resolution in the instruction
+    #[account(mut)]
+    pub second_stake_account: Option<AccountInfo<'info>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate that the account key is the Incinerator account
+    #[account(
+        mut,
+        constraint = incinerator_account.key() == incinerator::id()
+    )]
+    pub incinerator_account: AccountInfo<'info>,
+
+    /// CHECK: We validate that the account key is the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Lockup custodian named at init, if any. Has to sign whenever the lockup is still in force
+    pub custodian: Option<Signer<'info>>,
+
+    /// Required once attach_details has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump = details.bump
+    )]
+    pub details: Option<Account<'info, ResolutionDetails>>,
+
+    /// Required once open_vault has run. Funded with an approved payout, closed otherwise
+    #[account(
+        mut,
+        seeds = [b"vault", resolution_account.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Option<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStakelessResolution<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        constraint = resolution_account.is_stakeless() @ ResolutionErrorCode::InvalidStakeAccount,
+        constraint = !resolution_account.is_pooled() @ ResolutionErrorCode::InvalidStakePool,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// Required once attach_details has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump = details.bump
+    )]
+    pub details: Option<Account<'info, ResolutionDetails>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseOrphanedResolution<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Matched with has_one, it only has to be gone from the stake program
+    #[account(
+        constraint = is_orphaned(&stake_account) @ ResolutionErrorCode::StakeAccountNotClosed
+    )]
+    pub stake_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = stake_account,
+        constraint = !resolution_account.is_stakeless() @ ResolutionErrorCode::NoStakeAttached,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    /// CHECK: Required once split_stake has run, matched against the resolution in the instruction
+    pub second_stake_account: Option<AccountInfo<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// Required once attach_details has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump = details.bump
+    )]
+    pub details: Option<Account<'info, ResolutionDetails>>,
+
+    /// Required once open_vault has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"vault", resolution_account.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Option<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePoolResolution<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = withdraw_destination,
+        has_one = charity,
+        constraint = resolution_account.stake_pool == Some(stake_pool.key()) @ ResolutionErrorCode::InvalidStakePool,
+        constraint = resolution_account.pool_token_account == Some(pool_token_account.key()) @ ResolutionErrorCode::InvalidStakePool,
+        seeds = [b"resolution", owner.key().as_ref(), &resolution_account.nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: Account<'info, ResolutionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// Receives the payout, the owner unless another wallet was set at init
+    #[account(mut)]
+    pub withdraw_destination: Signer<'info>,
+
+    /// CHECK: We validate using has_one, it only receives the penalty lamports
+    #[account(mut)]
+    pub charity: AccountInfo<'info>,
+
+    /// CHECK: We validate using the resolution's stored stake pool
+    #[account(mut)]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's withdraw authority PDA, validated by the stake pool program
+    pub pool_withdraw_authority: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's reserve stake account, validated by the stake pool program
+    #[account(mut)]
+    pub reserve_stake: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pool_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: The pool manager's fee account, validated by the stake pool program
+    #[account(mut)]
+    pub manager_fee_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pool_mint: Account<'info, Mint>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub stake_history: Sysvar<'info, StakeHistory>,
+
+    /// CHECK: We validate that the account key is the Incinerator account
+    #[account(
+        mut,
+        constraint = incinerator_account.key() == incinerator::id()
+    )]
+    pub incinerator_account: AccountInfo<'info>,
+
+    /// CHECK: We validate the program ID in the instruction
+    #[account(
+        executable,
+        constraint = stake_program.key() == stake::program::ID @ ResolutionErrorCode::InvalidStakeProgram
+    )]
+    pub stake_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: We validate the program ID
+    #[account(
+        executable,
+        constraint = stake_pool_program.key() == stake_pool::ID @ ResolutionErrorCode::InvalidStakePoolProgram
+    )]
+    pub stake_pool_program: UncheckedAccount<'info>,
+
+    /// Required once attach_details has run, closed along with the resolution
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"details", resolution_account.key().as_ref()],
+        bump = details.bump
+    )]
+    pub details: Option<Account<'info, ResolutionDetails>>,
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
 pub struct ResolutionAccount {
     owner: Pubkey,
-    #[max_len(256)]
+    nonce: u64,
+    /// One of the CATEGORY_* constants, kept ahead of the strings so it sits at a fixed
+    /// offset for getProgramAccounts memcmp filters
+    category: u8,
+    #[max_len(MAX_TITLE_LEN)]
+    title: String,
+    #[max_len(MAX_TEXT_LEN)]
     text: String,
-    #[max_len(3)]
+    #[max_len(MAX_APPROVERS)]
     approvers: Vec<Pubkey>,
-    #[max_len(3)]
+    #[max_len(MAX_APPROVERS)]
     approved_by: Vec<Pubkey>,
+    #[max_len(MAX_APPROVERS, MAX_NOTE_LEN)]
+    approval_notes: Vec<String>,
+    #[max_len(MAX_APPROVERS)]
+    approved_at: Vec<i64>,
+    /// Part of the approve_with_signature message, bumped on every revocation so older
+    /// signatures can't be replayed
+    approval_nonce: u64,
+    /// Backup keys approve_resolution accepts in place of an approver
+    #[max_len(MAX_APPROVERS)]
+    approval_delegates: Vec<ApprovalDelegate>,
+    pending_extension: i64,
+    #[max_len(MAX_APPROVERS)]
+    extension_approved_by: Vec<Pubkey>,
+    extension_count: u8,
+    #[max_len(MAX_APPROVERS)]
+    emergency_release_approved_by: Vec<Pubkey>,
+    /// Approver that remove_approver is collecting consent to drop, if any
+    pending_removal: Option<Pubkey>,
+    #[max_len(MAX_APPROVERS)]
+    removal_consented_by: Vec<Pubkey>,
+    /// Timestamps after which the matching milestone_releases amount can be released
+    #[max_len(MAX_MILESTONES)]
+    milestones: Vec<i64>,
+    #[max_len(MAX_MILESTONES)]
+    milestone_releases: Vec<u64>,
+    /// Milestones are released in order, so this is also the index of the next one
+    milestones_released: u8,
+    /// Approvers who signed off on the next milestone
+    #[max_len(MAX_APPROVERS)]
+    milestone_approved_by: Vec<Pubkey>,
+    required_approvals: u8,
+    charity: Pubkey,
+    penalty_bps: u16,
+    /// Withdraw authority of the stake and where close pays out, the owner unless set at init
+    withdraw_destination: Pubkey,
+    /// Highest validator commission the owner accepts when delegating, None for no cap
+    max_commission_bps: Option<u16>,
+    /// Lockup custodian named at init, None when the resolution PDA is the custodian
+    custodian: Option<Pubkey>,
+    /// Lamports each approver in approved_by gets at close, escrowed in this account
+    approver_reward: u64,
+    /// Whether a ResolutionDetails PDA has to be closed along with this account
+    has_details: bool,
+    /// Whether approvers, including replacements, have to be in the Registry
+    require_registered_approvers: bool,
+    /// In lamports, not SOL, and covers both stake accounts once split
     stake_amount: u64,
     stake_account: Pubkey,
+    second_stake_account: Option<Pubkey>,
+    /// SPL stake pool the stake was deposited into by initialize_resolution_pool
+    stake_pool: Option<Pubkey>,
+    /// Token account of the resolution PDA holding the pool tokens
+    pool_token_account: Option<Pubkey>,
+    validator_vote_account: Pubkey,
     start_time: i64,
     end_time: i64,
+    /// Seconds after end_time during which approvals are still accepted
+    grace_period: i64,
+    /// When initiate_close ran, 0 while no two-phase close is pending
+    close_requested_at: i64,
+    /// Epoch initiate_close deactivated the stake in, finalize_close waits for it to end
+    close_requested_epoch: u64,
+    /// Slot the resolution was created in, for indexers
+    created_slot: u64,
+    /// Sequential id from the stats counter, stable for off-chain references unlike the PDA
+    id: u64,
+    bump: u8,
+    /// Seconds an approval keeps counting after approved_at, 0 for no expiry
+    approval_validity: i64,
+    /// Bump of the `["stake", resolution]` PDA when the stake account is one, 0 for keypairs
+    stake_bump: u8,
+    /// Whether an approved close pays into the Vault PDA instead of the recipient
+    has_vault: bool,
+    /// Set by flag_resolution, holds back an approved close until DISPUTE_WINDOW after flagged_at
+    flagged: bool,
+    /// Approver behind the latest flag, kept after it clears
+    flagged_by: Pubkey,
+    flagged_at: i64,
+    /// Index-aligned with approvers and summing to TOTAL_APPROVAL_WEIGHT, empty when every
+    /// approver counts once. required_approvals is in weight units when set
+    #[max_len(MAX_APPROVERS)]
+    weights: Vec<u8>,
+    /// Whether delegating to a vote account withdrawn by the owner or withdraw destination is
+    /// rejected, checked wherever max_commission_bps is
+    disallow_self_validator: bool,
+    /// Every approver that has flagged so far, each only gets one flag
+    #[max_len(MAX_APPROVERS)]
+    flaggers: Vec<Pubkey>,
+}
+
+/// ResolutionAccount as the first release laid it out, at `["resolution", owner]`. It shares
+/// the current discriminator, so it's told apart by its size. Only migrate_resolution reads it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LegacyResolutionAccount {
+    pub owner: Pubkey,
+    pub text: String,
+    pub approvers: Vec<Pubkey>,
+    pub approved_by: Vec<Pubkey>,
+    pub stake_amount: u64,
+    pub stake_account: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub bump: u8,
+}
+
+impl LegacyResolutionAccount {
+    /// Discriminator plus the first release's InitSpace: 256 bytes of text and three approvers
+    pub const LEN: usize = 8 + 32 + (4 + 256) + 2 * (4 + 3 * 32) + 8 + 32 + 8 + 8 + 1;
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() != Self::LEN || !data.starts_with(&ResolutionAccount::DISCRIMINATOR) {
+            return Err(ResolutionErrorCode::InvalidLegacyResolution.into());
+        }
+
+        Self::deserialize(&mut &data[8..])
+            .map_err(|_| ResolutionErrorCode::InvalidLegacyResolution.into())
+    }
+}
+
+/// Extended description of a resolution, at `["details", resolution]`
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct ResolutionDetails {
+    resolution: Pubkey,
+    #[max_len(MAX_DETAILS_LEN)]
+    text: String,
+    bump: u8,
+}
+
+/// Releases an approved payout linearly, at `["vault", resolution]`
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Vault {
+    resolution: Pubkey,
+    /// Paid the rent, which goes back once everything has been claimed
+    owner: Pubkey,
+    beneficiary: Pubkey,
+    /// In lamports, on top of the vault's rent
+    total_amount: u64,
+    claimed_amount: u64,
+    /// When close_resolution funded the vault, 0 until then
+    start_time: i64,
+    /// Seconds from start_time until the whole amount has unlocked
+    duration: i64,
+    bump: u8,
+}
+
+impl Vault {
+    pub fn unlocked_amount(&self, now: i64) -> u64 {
+        unlocked_amount(self.total_amount, self.start_time, self.duration, now)
+    }
+}
+
+/// Aggregates across every open resolution, in lamports
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Stats {
+    total_locked: u64,
+    active_count: u64,
+    /// Id the next resolution gets, never decremented so ids stay unique
+    next_id: u64,
+    bump: u8,
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct ProgramConfig {
+    authority: Pubkey,
+    paused: bool,
+    /// Seconds an owner has to wait between creating two resolutions
+    creation_cooldown: i64,
+    bump: u8,
+}
+
+/// Per-owner bookkeeping for the creation cooldown, at `["profile", owner]`
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct OwnerProfile {
+    owner: Pubkey,
+    last_created_at: i64,
+    bump: u8,
+}
+
+/// Allowlist of approvers curated by its authority, at `["registry"]`
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Registry {
+    authority: Pubkey,
+    #[max_len(MAX_REGISTRY_SIZE)]
+    approvers: Vec<Pubkey>,
+    bump: u8,
+}
+
+/// Programs whose PDAs may approve through approve_via_cpi, curated by its authority,
+/// at `["approver_programs"]`
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct ApproverPrograms {
+    authority: Pubkey,
+    #[max_len(MAX_APPROVER_PROGRAMS)]
+    programs: Vec<Pubkey>,
+    bump: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct SweepDust<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The only guard: every close withdraws the stake before it gives up the PDA, so being owned
+    /// by the system program means the stake is gone. A live resolution is still owned by this
+    /// program and fails here with AccountNotSystemOwned
+    #[account(
+        mut,
+        seeds = [b"resolution", owner.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub resolution_account: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct WithdrawablePreview {
+    pub total_lamports: u64,
+    pub rent_exempt_reserve: u64,
+}
+
+/// Approvals that count right now against `required_approvals`, both in weight on
+/// weighted resolutions. An emergency release isn't reflected here
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ApprovalProgress {
+    pub approved: u16,
+    pub required: u16,
+    pub threshold_met: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResolutionAddress {
+    pub address: Pubkey,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct ApprovalDelegate {
+    pub approver: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolutionStatus {
+    ActiveLocked,
+    ActiveApproved,
+    ExpiredUnapproved,
+}
+
+/// Versioned summary returned by get_resolution_summary. Every field is byte aligned so
+/// the `repr(C)` layout has no padding and matches the serialized bytes:
+/// version (1), owner (32), stake_amount (8, LE), end_time (8, LE), approval_count (1),
+/// status (1, the ResolutionStatus discriminant)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResolutionSummary {
+    pub version: u8,
+    pub owner: [u8; 32],
+    pub stake_amount: [u8; 8],
+    pub end_time: [u8; 8],
+    pub approval_count: u8,
+    pub status: u8,
+}
+
+impl ResolutionSummary {
+    pub const LEN: usize = std::mem::size_of::<ResolutionSummary>();
+
+    fn new(resolution: &ResolutionAccount, now: i64) -> Self {
+        ResolutionSummary {
+            version: RESOLUTION_SUMMARY_VERSION,
+            owner: resolution.owner.to_bytes(),
+            stake_amount: resolution.stake_amount.to_le_bytes(),
+            end_time: resolution.end_time.to_le_bytes(),
+            approval_count: resolution.approved_by.len() as u8,
+            status: resolution.status(now) as u8,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[0] = self.version;
+        bytes[1..33].copy_from_slice(&self.owner);
+        bytes[33..41].copy_from_slice(&self.stake_amount);
+        bytes[41..49].copy_from_slice(&self.end_time);
+        bytes[49] = self.approval_count;
+        bytes[50] = self.status;
+        bytes
+    }
+}
+
+/// Whether close_resolution would go through, and if not the error it would fail with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloseEligibility {
+    Ok,
+    LockupInForce,
+    StakeNotDeactivated,
+    NotEnoughApprovals,
+    Flagged,
+}
+
+impl CloseEligibility {
+    fn ensure(self) -> Result<()> {
+        match self {
+            CloseEligibility::Ok => Ok(()),
+            CloseEligibility::LockupInForce => Err(ResolutionErrorCode::LockupInForce.into()),
+            CloseEligibility::StakeNotDeactivated => {
+                msg!("Call deactivate_resolution_stake and wait for the cooldown to finish");
+                Err(ResolutionErrorCode::StakeNotDeactivated.into())
+            }
+            CloseEligibility::NotEnoughApprovals => {
+                msg!("Approvals are still accepted until the grace period runs out");
+                Err(ResolutionErrorCode::NotEnoughApprovals.into())
+            }
+            CloseEligibility::Flagged => Err(ResolutionErrorCode::ResolutionFlagged.into()),
+        }
+    }
+}
+
+impl ResolutionAccount {
+    // Only approvals that haven't expired count towards required_approvals
+    pub fn is_approved(&self, now: i64) -> bool {
+        self.approval_progress(now).threshold_met
+    }
+
+    pub fn approval_progress(&self, now: i64) -> ApprovalProgress {
+        let approved = self.valid_approval_weight(now);
+        let required = self.required_approvals as u16;
+
+        ApprovalProgress {
+            approved,
+            required,
+            threshold_met: approved >= required,
+        }
+    }
+
+    // Weight of the approvals that haven't expired, their count without weights
+    pub fn valid_approval_weight(&self, now: i64) -> u16 {
+        if self.weights.is_empty() {
+            return self.valid_approval_count(now) as u16;
+        }
+
+        self.approved_by
+            .iter()
+            .zip(&self.approved_at)
+            .filter(|(_, approved_at)| !self.is_stale(**approved_at, now))
+            .map(|(approver, _)| self.weight_of(approver))
+            .sum()
+    }
+
+    // 1 for every approver without weights, 0 for anyone who isn't an approver
+    pub fn weight_of(&self, approver: &Pubkey) -> u16 {
+        match self.approvers.iter().position(|key| key == approver) {
+            Some(index) => self.weights.get(index).map_or(1, |weight| *weight as u16),
+            None => 0,
+        }
+    }
+
+    // Combined weight of a sign-off list such as extension_approved_by
+    pub fn total_weight(&self, approvers: &[Pubkey]) -> u16 {
+        approvers
+            .iter()
+            .map(|approver| self.weight_of(approver))
+            .sum()
+    }
+
+    // Approvals older than approval_validity are stale, 0 keeps them valid forever
+    pub fn valid_approval_count(&self, now: i64) -> usize {
+        if self.approval_validity == 0 {
+            return self.approved_by.len();
+        }
+
+        self.approved_at
+            .iter()
+            .filter(|approved_at| !self.is_stale(**approved_at, now))
+            .count()
+    }
+
+    pub fn is_stale(&self, approved_at: i64, now: i64) -> bool {
+        self.approval_validity > 0 && now.saturating_sub(approved_at) > self.approval_validity
+    }
+
+    // A flag stops counting once DISPUTE_WINDOW has passed, even if nobody cleared it
+    // Remembers every flagger rather than only the latest, otherwise two approvers taking
+    // turns could keep the close held back indefinitely
+    pub fn record_flag(&mut self, flagger: Pubkey, now: i64) -> Result<()> {
+        if !self.approvers.contains(&flagger) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+
+        if self.is_flagged(now) || self.flaggers.contains(&flagger) {
+            return Err(ResolutionErrorCode::AlreadyFlagged.into());
+        }
+
+        self.flaggers.push(flagger);
+        self.flagged = true;
+        self.flagged_by = flagger;
+        self.flagged_at = now;
+
+        Ok(())
+    }
+
+    pub fn is_flagged(&self, now: i64) -> bool {
+        self.flagged && now < self.flagged_at + DISPUTE_WINDOW
+    }
+
+    // The owner's way out of a dispute: every approval given so far is dropped along with
+    // the flag, so the approved path only opens again once enough approvers sign off anew.
+    // The flagger stays in flaggers, same as after clear_flag
+    pub fn resolve_flag(&mut self, now: i64) -> Result<()> {
+        if !self.is_flagged(now) {
+            return Err(ResolutionErrorCode::NotFlagged.into());
+        }
+
+        self.flagged = false;
+        self.approved_by.clear();
+        self.approval_notes.clear();
+        self.approved_at.clear();
+        self.milestone_approved_by.clear();
+        // off-chain signatures collected before the dispute can't bring the approvals back
+        self.approval_nonce += 1;
+
+        Ok(())
+    }
+
+    // Partial withdrawals and milestones pay out of an approved resolution as well, so a
+    // standing flag holds them back just like the approved close
+    pub fn ensure_not_flagged(&self, now: i64) -> Result<()> {
+        if self.is_flagged(now) {
+            return Err(ResolutionErrorCode::ResolutionFlagged.into());
+        }
+
+        Ok(())
+    }
+
+    // Whether approver's approval is recorded and still counts towards required_approvals
+    pub fn has_valid_approval(&self, approver: &Pubkey, now: i64) -> bool {
+        self.approved_by.contains(approver) && !self.is_stale(self.approved_at_of(approver), now)
+    }
+
+    // Approvals still count until the grace period after end_time runs out
+    pub fn approval_deadline(&self) -> i64 {
+        self.end_time.saturating_add(self.grace_period)
+    }
+
+    pub fn is_stakeless(&self) -> bool {
+        self.stake_account == Pubkey::default()
+    }
+
+    // The first release had no notion of most of today's settings, so they come out the
+    // way that release behaved: every approver has to approve, the stake goes back to the
+    // owner, and a failed goal pays no penalty beyond the burn of the rewards
+    pub fn from_legacy(
+        legacy: LegacyResolutionAccount,
+        nonce: u64,
+        bump: u8,
+        id: u64,
+        validator_vote_account: Pubkey,
+        clock: &Clock,
+    ) -> Self {
+        let approval_count = legacy.approved_by.len();
+
+        Self {
+            owner: legacy.owner,
+            nonce,
+            category: CATEGORY_OTHER,
+            title: String::new(),
+            text: legacy.text,
+            required_approvals: legacy.approvers.len() as u8,
+            approvers: legacy.approvers,
+            approved_by: legacy.approved_by,
+            // the first release didn't keep notes or timestamps, so approvals count from now
+            approval_notes: vec![String::new(); approval_count],
+            approved_at: vec![clock.unix_timestamp; approval_count],
+            approval_nonce: 0,
+            approval_delegates: Vec::new(),
+            pending_extension: 0,
+            extension_approved_by: Vec::new(),
+            extension_count: 0,
+            emergency_release_approved_by: Vec::new(),
+            pending_removal: None,
+            removal_consented_by: Vec::new(),
+            milestones: Vec::new(),
+            milestone_releases: Vec::new(),
+            milestones_released: 0,
+            milestone_approved_by: Vec::new(),
+            // never paid with penalty_bps at 0, the incinerator just fills the slot
+            charity: incinerator::ID,
+            penalty_bps: 0,
+            withdraw_destination: legacy.owner,
+            max_commission_bps: None,
+            custodian: None,
+            approver_reward: 0,
+            has_details: false,
+            require_registered_approvers: false,
+            stake_amount: legacy.stake_amount,
+            stake_account: legacy.stake_account,
+            second_stake_account: None,
+            stake_pool: None,
+            pool_token_account: None,
+            validator_vote_account,
+            start_time: legacy.start_time,
+            end_time: legacy.end_time,
+            grace_period: 0,
+            close_requested_at: 0,
+            close_requested_epoch: 0,
+            created_slot: clock.slot,
+            id,
+            bump,
+            approval_validity: 0,
+            stake_bump: 0,
+            has_vault: false,
+            flagged: false,
+            flagged_by: Pubkey::default(),
+            flagged_at: 0,
+            weights: Vec::new(),
+            disallow_self_validator: false,
+            flaggers: Vec::new(),
+        }
+    }
+
+    // Approvers act for themselves, a registered delegate acts for the approver that named it
+    pub fn approver_for(&self, signer: &Pubkey) -> Pubkey {
+        if self.approvers.contains(signer) {
+            return *signer;
+        }
+
+        self.approval_delegates
+            .iter()
+            .find(|entry| &entry.delegate == signer)
+            .map_or(*signer, |entry| entry.approver)
+    }
+
+    // Anchor re-derives the canonical bump on every call, so a stored bump that disagrees
+    // means the seeds changed since the account was created
+    pub fn ensure_bump(&self, bump: u8) -> Result<()> {
+        if self.bump != bump {
+            return Err(ResolutionErrorCode::BumpMismatch.into());
+        }
+
+        Ok(())
+    }
+
+    // Index, release time and amount of the first milestone not released yet
+    pub fn next_milestone(&self) -> Option<(u8, i64, u64)> {
+        let index = self.milestones_released;
+        let release_time = *self.milestones.get(index as usize)?;
+        let amount = *self.milestone_releases.get(index as usize)?;
+        Some((index, release_time, amount))
+    }
+
+    // Pooled resolutions are stakeless too, their SOL sits in the stake pool instead
+    pub fn is_pooled(&self) -> bool {
+        self.stake_pool.is_some()
+    }
+
+    // requires every current approver, the threshold doesn't apply
+    pub fn is_emergency_released(&self) -> bool {
+        self.approvers
+            .iter()
+            .all(|approver| self.emergency_release_approved_by.contains(approver))
+    }
+
+    // When approver's recorded approval was given, 0 if they haven't approved
+    pub fn approved_at_of(&self, approver: &Pubkey) -> i64 {
+        self.approved_by
+            .iter()
+            .position(|approved| approved == approver)
+            .and_then(|index| self.approved_at.get(index).copied())
+            .unwrap_or(0)
+    }
+
+    // Whether record_approval would take an approval from approver right now
+    pub fn can_approve(&self, approver: &Pubkey, now: i64) -> bool {
+        if now >= self.approval_deadline() || !self.approvers.contains(approver) {
+            return false;
+        }
+
+        match self
+            .approved_by
+            .iter()
+            .position(|approved| approved == approver)
+        {
+            Some(index) => self.is_stale(self.approved_at.get(index).copied().unwrap_or(0), now),
+            None => true,
+        }
+    }
+
+    pub fn pending_approvers(&self) -> Vec<Pubkey> {
+        self.approvers
+            .iter()
+            .filter(|approver| !self.approved_by.contains(approver))
+            .copied()
+            .collect()
+    }
+
+    // mirrors the branches close_resolution takes
+    pub fn status(&self, now: i64) -> ResolutionStatus {
+        if self.is_approved(now) || self.is_emergency_released() {
+            ResolutionStatus::ActiveApproved
+        } else if now < self.approval_deadline() {
+            ResolutionStatus::ActiveLocked
+        } else {
+            ResolutionStatus::ExpiredUnapproved
+        }
+    }
+}
+
+impl Stats {
+    // Hands out the next resolution id, the mutable borrow keeps concurrent inits serialized
+    fn record_opened(&mut self, stake_amount: u64) -> u64 {
+        self.total_locked = self.total_locked.saturating_add(stake_amount);
+        self.active_count = self.active_count.saturating_add(1);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn record_closed(&mut self, stake_amount: u64) {
+        self.total_locked = self.total_locked.saturating_sub(stake_amount);
+        self.active_count = self.active_count.saturating_sub(1);
+    }
+}
+
+// Shared by both initialize instructions so the stored state is identical
+#[allow(clippy::too_many_arguments)]
+fn create_resolution(
+    accounts: &mut InitializeResolution,
+    bumps: &InitializeResolutionBumps,
+    nonce: u64,
+    approvers: Vec<Pubkey>,
+    stake_amount: u64,
+    lockup_duration: i64,
+    title: String,
+    text: String,
+    threshold: u8,
+    charity: Pubkey,
+    penalty_bps: u16,
+    category: u8,
+    withdraw_destination: Option<Pubkey>,
+    max_commission_bps: Option<u16>,
+    grace_period: i64,
+    custodian: Option<Pubkey>,
+    approver_reward: u64,
+    require_registered_approvers: bool,
+    milestones: Vec<i64>,
+    milestone_releases: Vec<u64>,
+    weights: Vec<u8>,
+    disallow_self_validator: bool,
+) -> Result<()> {
+    validate_approver_set(&accounts.owner.key(), &approvers)?;
+
+    // curated communities only accept approvers from the registry
+    if require_registered_approvers {
+        ensure_registered(
+            accounts
+                .registry
+                .as_ref()
+                .map(|registry| &registry.approvers[..]),
+            &approvers,
+        )?;
+    }
+
+    if title.trim().is_empty() || title.len() > MAX_TITLE_LEN as usize {
+        return Err(ResolutionErrorCode::InvalidTitle.into());
+    }
+
+    validate_text(&text)?;
+
+    // threshold is the number of approvals needed to close early
+    validate_threshold(&approvers, &weights, threshold)?;
+
+    validate_charity(&accounts.owner.key(), &approvers, &charity, penalty_bps)?;
+
+    if category >= CATEGORY_COUNT {
+        return Err(ResolutionErrorCode::InvalidCategory.into());
+    }
+
+    // a separate cold wallet can hold the withdraw authority, otherwise it's the owner
+    let withdraw_destination = withdraw_destination.unwrap_or(accounts.owner.key());
+    if withdraw_destination == Pubkey::default() {
+        return Err(ResolutionErrorCode::InvalidWithdrawDestination.into());
+    }
+    ensure_not_approver(&approvers, &withdraw_destination)?;
+
+    // A zero stake is a pure social commitment, there is no stake account to create
+    let stake_account = if stake_amount == 0 {
+        None
+    } else {
+        ensure_validator_voting(&accounts.validator_vote_account)?;
+
+        // Only checked when (re)delegating, the validator can still raise it afterwards
+        if max_commission_bps.is_some_and(|max_commission_bps| max_commission_bps > BPS_DENOMINATOR)
+        {
+            return Err(ResolutionErrorCode::InvalidCommissionCap.into());
+        }
+        ensure_commission_within(&accounts.validator_vote_account, max_commission_bps)?;
+
+        if disallow_self_validator {
+            ensure_not_self_validator(
+                &accounts.validator_vote_account,
+                &[accounts.owner.key(), withdraw_destination],
+            )?;
+        }
+
+        // delegate_stake fails with an opaque error below the network minimum
+        let minimum_delegation = get_minimum_delegation()?;
+        if stake_amount < minimum_delegation {
+            msg!("Minimum delegation: {}", minimum_delegation);
+            return Err(ResolutionErrorCode::StakeAmountTooLow.into());
+        }
+
+        if stake_amount > MAX_STAKE_AMOUNT {
+            return Err(ResolutionErrorCode::StakeAmountTooHigh.into());
+        }
+
+        // either a fresh keypair signed the transaction, or the program signs for its PDA
+        match (&accounts.stake_account, &accounts.stake_pda) {
+            (Some(stake_account), None) => Some((stake_account.key(), None)),
+            (None, Some(stake_pda)) => {
+                let (expected, stake_bump) = Pubkey::find_program_address(
+                    &[b"stake", accounts.resolution_account.key().as_ref()],
+                    &crate::ID,
+                );
+                if stake_pda.key() != expected {
+                    return Err(ResolutionErrorCode::InvalidStakeAccount.into());
+                }
+                Some((expected, Some(stake_bump)))
+            }
+            _ => return Err(ResolutionErrorCode::InvalidStakeAccount.into()),
+        }
+    };
+    let stake_bump = stake_account.and_then(|(_, stake_bump)| stake_bump);
+    let stake_account = stake_account.map(|(stake_account, _)| stake_account);
+
+    if !(0..=MAX_GRACE_PERIOD).contains(&grace_period) {
+        return Err(ResolutionErrorCode::InvalidGracePeriod.into());
+    }
+
+    // too short a lockup defeats the purpose of committing to a goal
+    if !(MIN_LOCKUP_DURATION..=MAX_LOCKUP_DURATION).contains(&lockup_duration) {
+        return Err(ResolutionErrorCode::InvalidLockupDuration.into());
+    }
+
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let lockup_end = now
+        .checked_add(lockup_duration)
+        .ok_or(ResolutionErrorCode::InvalidLockupDuration)?;
+
+    // A fresh profile has last_created_at 0, so an owner's first resolution is never held up
+    let profile = &mut accounts.profile;
+    let creation_cooldown = accounts.config.creation_cooldown;
+    let cooldown_end = profile.last_created_at.saturating_add(creation_cooldown);
+    if creation_cooldown > 0 && profile.last_created_at != 0 && now < cooldown_end {
+        msg!("Next resolution can be created at {}", cooldown_end);
+        return Err(ResolutionErrorCode::CreationCooldown.into());
+    }
+    profile.owner = accounts.owner.key();
+    profile.last_created_at = now;
+    profile.bump = bumps.profile;
+
+    validate_milestones(
+        &milestones,
+        &milestone_releases,
+        stake_amount,
+        now,
+        lockup_end,
+    )?;
+
+    // Every approver's reward is escrowed up front, whatever isn't earned goes back at close
+    let reward_escrow = approver_reward
+        .checked_mul(approvers.len() as u64)
+        .ok_or(ResolutionErrorCode::InvalidApproverReward)?;
+    if reward_escrow > 0 {
+        invoke(
+            &transfer(
+                accounts.owner.key,
+                &accounts.resolution_account.key(),
+                reward_escrow,
+            ),
+            &[
+                accounts.owner.to_account_info(),
+                accounts.resolution_account.to_account_info(),
+                accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    if stake_account.is_some() {
+        delegate_new_stake(
+            accounts,
+            bumps.resolution_account,
+            stake_bump,
+            nonce,
+            stake_amount,
+            lockup_end,
+            withdraw_destination,
+            custodian,
+        )?;
+    }
+
+    let resolution = &mut accounts.resolution_account;
+
+    resolution.owner = accounts.owner.key();
+    resolution.nonce = nonce;
+    resolution.category = category;
+    resolution.title = title;
+    resolution.text = text;
+    resolution.approvers = approvers;
+    resolution.approved_by = [].to_vec();
+    resolution.approval_notes = [].to_vec();
+    resolution.approved_at = [].to_vec();
+    resolution.approval_nonce = 0;
+    resolution.pending_extension = 0;
+    resolution.extension_approved_by = [].to_vec();
+    resolution.extension_count = 0;
+    resolution.emergency_release_approved_by = [].to_vec();
+    resolution.pending_removal = None;
+    resolution.removal_consented_by = [].to_vec();
+    resolution.milestones = milestones;
+    resolution.milestone_releases = milestone_releases;
+    resolution.milestones_released = 0;
+    resolution.milestone_approved_by = [].to_vec();
+    // Captured once so later edits to `approvers` can't change what close requires
+    resolution.required_approvals = threshold;
+    resolution.weights = weights;
+    resolution.disallow_self_validator = disallow_self_validator;
+    resolution.charity = charity;
+    resolution.penalty_bps = penalty_bps;
+    resolution.withdraw_destination = withdraw_destination;
+    resolution.max_commission_bps = max_commission_bps;
+    resolution.custodian = custodian;
+    resolution.approver_reward = approver_reward;
+    resolution.has_details = false;
+    resolution.require_registered_approvers = require_registered_approvers;
+    resolution.stake_amount = stake_amount;
+    resolution.stake_account = stake_account.unwrap_or_default();
+    resolution.second_stake_account = None;
+    resolution.stake_pool = None;
+    resolution.pool_token_account = None;
+    resolution.validator_vote_account = match stake_account {
+        Some(_) => accounts.validator_vote_account.key(),
+        None => Pubkey::default(),
+    };
+    resolution.start_time = now;
+    resolution.end_time = lockup_end;
+    resolution.grace_period = grace_period;
+    resolution.created_slot = clock.slot;
+    resolution.id = accounts.stats.record_opened(stake_amount);
+    resolution.bump = bumps.resolution_account;
+    resolution.approval_validity = 0;
+    resolution.stake_bump = stake_bump.unwrap_or_default();
+    resolution.has_vault = false;
+    resolution.flagged = false;
+    resolution.flagged_by = Pubkey::default();
+    resolution.flagged_at = 0;
+    resolution.flaggers = Vec::new();
+
+    emit!(ResolutionInitialized {
+        resolution: resolution.key(),
+        owner: resolution.owner,
+        stake_account: resolution.stake_account,
+        stake_lamports: stake_amount,
+        end_time: lockup_end,
+        created_slot: clock.slot,
+        id: resolution.id,
+    });
+
+    Ok(())
+}
+
+// Creates, initializes and delegates the stake account of a staked resolution. stake_bump
+// is set when the stake account is the ["stake", resolution] PDA rather than a keypair
+#[allow(clippy::too_many_arguments)]
+fn delegate_new_stake(
+    accounts: &InitializeResolution,
     bump: u8,
+    stake_bump: Option<u8>,
+    nonce: u64,
+    stake_amount: u64,
+    lockup_end: i64,
+    withdraw_destination: Pubkey,
+    custodian: Option<Pubkey>,
+) -> Result<()> {
+    let stake_account = match stake_bump {
+        None => accounts
+            .stake_account
+            .as_ref()
+            .map(|stake_account| stake_account.to_account_info()),
+        Some(_) => accounts
+            .stake_pda
+            .as_ref()
+            .map(|stake_pda| stake_pda.to_account_info()),
+    }
+    .ok_or(ResolutionErrorCode::InvalidStakeAccount)?;
+
+    // The resolution PDA stakes, the withdraw destination withdraws
+    let authorized = Authorized {
+        staker: accounts.resolution_account.key(),
+        withdrawer: withdraw_destination,
+    };
+
+    // Configure Lockup for stake account
+    // Set custodian to resolution PDA account, or to the custodian the owner named.
+    // A named custodian can lift the lockup directly through the stake program together
+    // with the withdraw authority, without this program or the approvers being involved,
+    // so naming a wallet the owner also controls turns the lockup into a formality
+    let lockup = Lockup {
+        unix_timestamp: lockup_end,
+        epoch: 0,
+        custodian: custodian.unwrap_or(accounts.resolution_account.key()),
+    };
+
+    // Calculate balance for stake account
+    let rent = Rent::get()?;
+    let stake_space = StakeStateV2::size_of();
+    let lamports = rent
+        .minimum_balance(stake_space)
+        .checked_add(stake_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // A keypair stake account signed the transaction itself, the PDA needs the program to sign
+    let resolution_key = accounts.resolution_account.key();
+    let stake_bump_bytes = [stake_bump.unwrap_or_default()];
+    let stake_seeds: &[&[u8]] = &[b"stake", resolution_key.as_ref(), &stake_bump_bytes];
+    let stake_signer_seeds: &[&[&[u8]]] = match stake_bump {
+        Some(_) => &[stake_seeds],
+        None => &[],
+    };
+
+    invoke_signed(
+        &create_account(
+            accounts.owner.key,
+            stake_account.key,
+            lamports,
+            stake_space as u64,
+            &stake::program::ID,
+        ),
+        &[
+            accounts.owner.to_account_info(),
+            stake_account.to_account_info(),
+            accounts.system_program.to_account_info(),
+        ],
+        stake_signer_seeds,
+    )?;
+
+    // Initialize stake account
+    invoke(
+        &initialize(stake_account.key, &authorized, &lockup),
+        &[
+            stake_account.to_account_info(),
+            accounts.rent.to_account_info(),
+        ],
+    )?;
+
+    let nonce_bytes = nonce.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"resolution",
+        accounts.owner.key.as_ref(),
+        &nonce_bytes,
+        &[bump],
+    ]];
+
+    // Delegate stake
+    invoke_signed(
+        &delegate_stake(
+            stake_account.key,
+            &accounts.resolution_account.key(),
+            accounts.validator_vote_account.key,
+        ),
+        &[
+            stake_account.to_account_info(),
+            accounts.validator_vote_account.to_account_info(),
+            accounts.clock.to_account_info(),
+            accounts.stake_history.to_account_info(),
+            accounts.stake_config.to_account_info(),
+            accounts.resolution_account.to_account_info(),
+        ],
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+// Shared by every instruction that sets or changes the approvers
+// Without weights every approver counts once and threshold is a plain M-of-N. Weights have to
+// be non-zero, one per approver and add up to TOTAL_APPROVAL_WEIGHT, with threshold in the same
+// units
+fn validate_threshold(approvers: &[Pubkey], weights: &[u8], threshold: u8) -> Result<()> {
+    if weights.is_empty() {
+        if threshold == 0 || threshold as usize > approvers.len() {
+            return Err(ResolutionErrorCode::InvalidThreshold.into());
+        }
+
+        return Ok(());
+    }
+
+    let total: u16 = weights.iter().map(|weight| *weight as u16).sum();
+    if weights.len() != approvers.len()
+        || weights.contains(&0)
+        || total != TOTAL_APPROVAL_WEIGHT as u16
+    {
+        return Err(ResolutionErrorCode::InvalidWeights.into());
+    }
+
+    if threshold == 0 || threshold > TOTAL_APPROVAL_WEIGHT {
+        return Err(ResolutionErrorCode::InvalidThreshold.into());
+    }
+
+    Ok(())
+}
+
+fn validate_approver_set(owner: &Pubkey, approvers: &[Pubkey]) -> Result<()> {
+    if approvers.is_empty() || approvers.len() > MAX_APPROVERS as usize {
+        return Err(ResolutionErrorCode::InvalidNumApprovers.into());
+    }
+
+    // approvers must be unique and the owner can't be one of them, checked in a single pass
+    let mut unique_approvers = BTreeSet::new();
+    for approver in approvers {
+        if approver == owner || !unique_approvers.insert(approver) {
+            return Err(ResolutionErrorCode::InvalidApprover.into());
+        }
+    }
+
+    Ok(())
+}
+
+// Shared by create_resolution and update_charity
+fn validate_charity(
+    owner: &Pubkey,
+    approvers: &[Pubkey],
+    charity: &Pubkey,
+    penalty_bps: u16,
+) -> Result<()> {
+    // charity must be a third party, otherwise failing costs nothing
+    if charity == owner || approvers.contains(charity) {
+        return Err(ResolutionErrorCode::InvalidCharity.into());
+    }
+
+    if penalty_bps > BPS_DENOMINATOR {
+        return Err(ResolutionErrorCode::InvalidPenalty.into());
+    }
+
+    Ok(())
+}
+
+// The account only has room for MAX_TEXT_LEN bytes, and multibyte characters
+// would overrun a char count, so the limit is on String::len
+fn validate_text(text: &str) -> Result<()> {
+    if text.trim().is_empty() || text.len() > MAX_TEXT_LEN as usize {
+        return Err(ResolutionErrorCode::InvalidText.into());
+    }
+
+    Ok(())
+}
+
+// Approvers have to stay disinterested, so none of them may receive the stake
+fn ensure_not_approver(approvers: &[Pubkey], payee: &Pubkey) -> Result<()> {
+    if approvers.contains(payee) {
+        return Err(ResolutionErrorCode::InvalidApprover.into());
+    }
+
+    Ok(())
+}
+
+// Lamports above the rent exempt reserve and the principal. Saturates to 0 while the
+// balance is still short of that, e.g. after a partial withdrawal
+fn accrued_rewards(stake_lamports: u64, rent_exempt_reserve: u64, stake_amount: u64) -> u64 {
+    stake_lamports
+        .saturating_sub(rent_exempt_reserve)
+        .saturating_sub(stake_amount)
+}
+
+// Linear share of total_amount unlocked by now, nothing before start_time and all of it
+// from start_time + duration on
+fn unlocked_amount(total_amount: u64, start_time: i64, duration: i64, now: i64) -> u64 {
+    let elapsed = now.saturating_sub(start_time);
+    if elapsed <= 0 {
+        return 0;
+    }
+    if elapsed >= duration {
+        return total_amount;
+    }
+
+    (total_amount as u128 * elapsed as u128 / duration as u128) as u64
+}
+
+// Withdraws amount from the stake to the withdraw destination while the stake account
+// stays open, and takes it off the locked principal. Shared by partial_withdraw and
+// release_milestone
+fn withdraw_unlocked(accounts: &mut PartialWithdraw, bump: u8, amount: u64) -> Result<()> {
+    accounts
+        .resolution_account
+        .ensure_not_flagged(Clock::get()?.unix_timestamp)?;
+
+    // The stake account stays open, so it has to keep its rent exempt reserve
+    let rent_exempt_reserve = Rent::get()?.minimum_balance(StakeStateV2::size_of());
+    let available = accounts
+        .stake_account
+        .lamports()
+        .saturating_sub(rent_exempt_reserve);
+
+    if amount == 0 || amount > available {
+        return Err(ResolutionErrorCode::InvalidWithdrawAmount.into());
+    }
+
+    let nonce_bytes = accounts.resolution_account.nonce.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"resolution",
+        accounts.owner.key.as_ref(),
+        &nonce_bytes,
+        &[bump],
+    ]];
+
+    let (custodian_key, custodian) =
+        lockup_custodian(&accounts.resolution_account, &accounts.custodian)?;
+
+    invoke_signed(
+        &withdraw(
+            &accounts.stake_account.key(),
+            &accounts.withdraw_destination.key(),
+            &accounts.withdraw_destination.key(),
+            amount,
+            custodian_key.as_ref(),
+        ),
+        &[
+            accounts.stake_account.to_account_info(),
+            accounts.withdraw_destination.to_account_info(),
+            accounts.clock.to_account_info(),
+            accounts.stake_history.to_account_info(),
+            accounts.withdraw_destination.to_account_info(),
+            custodian,
+        ],
+        signer_seeds,
+    )?;
+
+    // Keep stake_amount in line with what's left, in case approvals are revoked later
+    let resolution = &mut accounts.resolution_account;
+    let stake_amount = resolution.stake_amount.saturating_sub(amount);
+    // rewards withdrawn above the principal were never counted as locked
+    let unlocked = resolution.stake_amount - stake_amount;
+    resolution.stake_amount = stake_amount;
+    accounts.stats.total_locked = accounts.stats.total_locked.saturating_sub(unlocked);
+
+    Ok(())
+}
+
+// Milestones need a stake to release from, strictly increasing times inside the lockup
+// and releases that together stay within the stake
+fn validate_milestones(
+    milestones: &[i64],
+    milestone_releases: &[u64],
+    stake_amount: u64,
+    now: i64,
+    lockup_end: i64,
+) -> Result<()> {
+    if milestones.is_empty() && milestone_releases.is_empty() {
+        return Ok(());
+    }
+
+    if stake_amount == 0
+        || milestones.len() != milestone_releases.len()
+        || milestones.len() > MAX_MILESTONES as usize
+    {
+        return Err(ResolutionErrorCode::InvalidMilestones.into());
+    }
+
+    let mut previous = now;
+    for &milestone in milestones {
+        if milestone <= previous || milestone > lockup_end {
+            return Err(ResolutionErrorCode::InvalidMilestones.into());
+        }
+        previous = milestone;
+    }
+
+    let mut total: u64 = 0;
+    for &release in milestone_releases {
+        if release == 0 {
+            return Err(ResolutionErrorCode::InvalidMilestones.into());
+        }
+        total = total
+            .checked_add(release)
+            .ok_or(ResolutionErrorCode::InvalidMilestones)?;
+    }
+    if total > stake_amount {
+        return Err(ResolutionErrorCode::InvalidMilestones.into());
+    }
+
+    Ok(())
+}
+
+// Shared by approve_resolution and approve_with_signature
+fn record_approval(
+    resolution: &mut Account<ResolutionAccount>,
+    approver: Pubkey,
+    note: String,
+) -> Result<()> {
+    if note.len() > MAX_NOTE_LEN as usize {
+        return Err(ResolutionErrorCode::NoteTooLong.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    // past the grace period close_resolution takes the not-approved path anyway
+    if now >= resolution.approval_deadline() {
+        return Err(ResolutionErrorCode::ResolutionExpired.into());
+    }
+
+    // check if the approver is in the approvers list
+    if !resolution.approvers.contains(&approver) {
+        return Err(ResolutionErrorCode::InvalidApprover.into());
+    }
+
+    // a stale approval can be renewed in place, a valid one can't be given twice
+    if let Some(index) = resolution
+        .approved_by
+        .iter()
+        .position(|approved| approved == &approver)
+    {
+        let approved_at = resolution.approved_at.get(index).copied().unwrap_or(0);
+        if !resolution.is_stale(approved_at, now) {
+            return Err(ResolutionErrorCode::AlreadyApproved.into());
+        }
+
+        resolution.approval_notes[index] = note;
+        resolution.approved_at[index] = now;
+    } else {
+        resolution.approved_by.push(approver);
+        resolution.approval_notes.push(note);
+        resolution.approved_at.push(now);
+    }
+
+    emit!(ResolutionApproved {
+        resolution: resolution.key(),
+        owner: resolution.owner,
+        approver,
+        approved_at: now,
+    });
+
+    Ok(())
+}
+
+// The SlotHashes sysvar is a u64 entry count followed by (slot u64, hash) entries,
+// newest first
+fn most_recent_slot_hash(slot_hashes: &AccountInfo) -> Result<[u8; 32]> {
+    let data = slot_hashes.try_borrow_data()?;
+
+    let mut count = [0u8; 8];
+    count.copy_from_slice(data.get(..8).ok_or(ResolutionErrorCode::NoSlotHashes)?);
+    if u64::from_le_bytes(count) == 0 {
+        return Err(ResolutionErrorCode::NoSlotHashes.into());
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(data.get(16..48).ok_or(ResolutionErrorCode::NoSlotHashes)?);
+    Ok(hash)
+}
+
+// Partial Fisher-Yates shuffle, re-hashing the seed for every draw. Deterministic for a
+// given seed, so anyone can recompute the selection. count must not exceed the candidates
+pub fn pick_approvers(candidates: &[Pubkey], count: usize, seed: [u8; 32]) -> Vec<Pubkey> {
+    let mut pool = candidates.to_vec();
+    let mut seed = seed;
+
+    for i in 0..count {
+        seed = hashv(&[&seed]).to_bytes();
+        let mut draw = [0u8; 8];
+        draw.copy_from_slice(&seed[..8]);
+        let j = i + (u64::from_le_bytes(draw) % (pool.len() - i) as u64) as usize;
+        pool.swap(i, j);
+    }
+
+    pool.truncate(count);
+    pool
+}
+
+// What an approver signs for approve_with_signature:
+// resolution (32) || approval_nonce (8, LE) || approved_at (8, LE) || note (UTF-8),
+// approved_at being the approver's current approval or 0 for none
+pub fn approval_message(
+    resolution: &Pubkey,
+    approval_nonce: u64,
+    approved_at: i64,
+    note: &str,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(48 + note.len());
+    message.extend_from_slice(resolution.as_ref());
+    message.extend_from_slice(&approval_nonce.to_le_bytes());
+    message.extend_from_slice(&approved_at.to_le_bytes());
+    message.extend_from_slice(note.as_bytes());
+    message
+}
+
+// The runtime has already checked the Ed25519 instruction's signature by the time this
+// runs, so all that's left is making sure it signed the expected message with the
+// expected key
+fn verify_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    if current_index == 0 {
+        return Err(ResolutionErrorCode::InvalidResolutionSignature.into());
+    }
+
+    let instruction = load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+    if instruction.program_id != ed25519_program::ID {
+        return Err(ResolutionErrorCode::InvalidResolutionSignature.into());
+    }
+
+    let (signed_by, signed_message) = ed25519_signed_message(&instruction.data)?;
+    if signed_by != signer.as_ref() || signed_message != message {
+        return Err(ResolutionErrorCode::InvalidResolutionSignature.into());
+    }
+
+    Ok(())
+}
+
+// The instructions sysvar only lists top-level instructions, so the caller we can see is
+// the program at the root of the CPI chain, not necessarily the one invoking us directly.
+// A stack height above the transaction level rules out being called straight from the
+// transaction, where the allowlisted top-level program would just be some other instruction
+fn ensure_invoked_by_approver_program(
+    instructions_sysvar: &AccountInfo,
+    allowed: &[Pubkey],
+) -> Result<()> {
+    if get_stack_height() <= TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Err(ResolutionErrorCode::CpiRequired.into());
+    }
+
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let instruction = load_instruction_at_checked(current_index as usize, instructions_sysvar)?;
+    if !allowed.contains(&instruction.program_id) {
+        return Err(ResolutionErrorCode::ApproverProgramNotAllowed.into());
+    }
+
+    Ok(())
+}
+
+// Deactivated stake only cools down in the next epoch, so a close can't go through in the
+// same transaction as a deactivation of the same resolution. Caught up front so the user
+// gets ConflictingInstructions instead of a failure deep in the stake program
+fn ensure_no_deactivate_in_transaction(
+    instructions_sysvar: &AccountInfo,
+    resolution: &Pubkey,
+) -> Result<()> {
+    let mut index = 0;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions_sysvar) {
+        if deactivates_resolution(&instruction, resolution) {
+            return Err(ResolutionErrorCode::ConflictingInstructions.into());
+        }
+        index += 1;
+    }
+
+    Ok(())
+}
+
+fn deactivates_resolution(
+    instruction: &anchor_lang::solana_program::instruction::Instruction,
+    resolution: &Pubkey,
+) -> bool {
+    let deactivating = [
+        crate::instruction::DeactivateResolutionStake::DISCRIMINATOR,
+        crate::instruction::InitiateClose::DISCRIMINATOR,
+    ];
+
+    instruction.program_id == crate::ID
+        && deactivating
+            .iter()
+            .any(|discriminator| instruction.data.starts_with(discriminator))
+        && instruction
+            .accounts
+            .iter()
+            .any(|account| &account.pubkey == resolution)
+}
+
+// Parses Ed25519 program instruction data holding a single signature whose key and message
+// live in the instruction itself: a signature count and padding byte, then seven u16 offsets
+// (signature, its instruction index, public key, its instruction index, message, message
+// size, its instruction index)
+fn ed25519_signed_message(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    let read_u16 = |offset: usize| -> Result<u16> {
+        data.get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .ok_or(ResolutionErrorCode::InvalidResolutionSignature.into())
+    };
+
+    if data.first() != Some(&1) {
+        return Err(ResolutionErrorCode::InvalidResolutionSignature.into());
+    }
+
+    let signature_instruction_index = read_u16(4)?;
+    let public_key_offset = read_u16(6)? as usize;
+    let public_key_instruction_index = read_u16(8)?;
+    let message_offset = read_u16(10)? as usize;
+    let message_size = read_u16(12)? as usize;
+    let message_instruction_index = read_u16(14)?;
+
+    // u16::MAX means the data is in the Ed25519 instruction itself, anything else would let
+    // the key or message come from an instruction we didn't look at
+    if [
+        signature_instruction_index,
+        public_key_instruction_index,
+        message_instruction_index,
+    ]
+    .iter()
+    .any(|&index| index != u16::MAX)
+    {
+        return Err(ResolutionErrorCode::InvalidResolutionSignature.into());
+    }
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(ResolutionErrorCode::InvalidResolutionSignature)?;
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ResolutionErrorCode::InvalidResolutionSignature)?;
+
+    Ok((public_key, message))
+}
+
+// A missing registry means nobody is registered
+fn ensure_registered(registered: Option<&[Pubkey]>, approvers: &[Pubkey]) -> Result<()> {
+    let registered = registered.unwrap_or_default();
+    if approvers
+        .iter()
+        .any(|approver| !registered.contains(approver))
+    {
+        return Err(ResolutionErrorCode::ApproverNotRegistered.into());
+    }
+
+    Ok(())
+}
+
+// An attached details PDA has to close together with the resolution or its rent is stranded
+fn ensure_details_passed(
+    resolution: &ResolutionAccount,
+    details: &Option<Account<ResolutionDetails>>,
+) -> Result<()> {
+    if resolution.has_details && details.is_none() {
+        return Err(ResolutionErrorCode::MissingDetailsAccount.into());
+    }
+
+    Ok(())
+}
+
+// An opened vault has to be passed so close can fund it, or close it when nothing is streamed
+fn ensure_vault_passed(
+    resolution: &ResolutionAccount,
+    vault: &Option<Account<Vault>>,
+) -> Result<()> {
+    if resolution.has_vault && vault.is_none() {
+        return Err(ResolutionErrorCode::MissingVaultAccount.into());
+    }
+
+    Ok(())
+}
+
+// Lamports held by the resolution PDA above its rent exempt minimum, i.e. the reward escrow
+fn escrowed_lamports(account: &AccountInfo) -> Result<u64> {
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(account.data_len());
+    Ok(account.lamports().saturating_sub(rent_exempt_minimum))
+}
+
+// Pays every approver in approved_by their reward out of the escrow. Their accounts come
+// in as writable remaining accounts, in any order
+fn pay_approver_rewards(
+    resolution: &AccountInfo,
+    approver_reward: u64,
+    approved_by: &[Pubkey],
+    approver_accounts: &[AccountInfo],
+) -> Result<u64> {
+    let total = approver_reward
+        .checked_mul(approved_by.len() as u64)
+        .ok_or(ResolutionErrorCode::InsufficientRewardEscrow)?;
+    if total == 0 {
+        return Ok(0);
+    }
+
+    if escrowed_lamports(resolution)? < total {
+        return Err(ResolutionErrorCode::InsufficientRewardEscrow.into());
+    }
+
+    for approver in approved_by {
+        let approver_account = approver_accounts
+            .iter()
+            .find(|account| account.key == approver)
+            .ok_or(ResolutionErrorCode::MissingApproverAccount)?;
+
+        resolution.sub_lamports(approver_reward)?;
+        approver_account.add_lamports(approver_reward)?;
+    }
+
+    Ok(total)
+}
+
+// Returns the stake's lockup custodian and the account that signs for it. That's the
+// resolution PDA unless a custodian was named at init, which then has to be passed and
+// sign. A named custodian that isn't passed yields no key, leaving the stake program to
+// reject the CPI if the lockup is still in force; the PDA info is only a placeholder then
+fn lockup_custodian<'info>(
+    resolution: &Account<'info, ResolutionAccount>,
+    custodian: &Option<Signer<'info>>,
+) -> Result<(Option<Pubkey>, AccountInfo<'info>)> {
+    match (resolution.custodian, custodian) {
+        (None, _) => Ok((Some(resolution.key()), resolution.to_account_info())),
+        (Some(key), Some(custodian)) if custodian.key() == key => {
+            Ok((Some(key), custodian.to_account_info()))
+        }
+        (Some(_), Some(_)) => Err(ResolutionErrorCode::InvalidCustodian.into()),
+        (Some(_), None) => Ok((None, resolution.to_account_info())),
+    }
+}
+
+// Resolves the optional second stake account against the one recorded at split time
+fn second_stake_account<'a, 'info>(
+    resolution: &ResolutionAccount,
+    second_stake_account: &'a Option<AccountInfo<'info>>,
+) -> Result<Option<&'a AccountInfo<'info>>> {
+    match (resolution.second_stake_account, second_stake_account) {
+        (None, _) => Ok(None),
+        (Some(expected), Some(account)) if account.key() == expected => Ok(Some(account)),
+        _ => Err(ResolutionErrorCode::InvalidStakeAccount.into()),
+    }
+}
+
+// A fully withdrawn stake account is garbage collected, which leaves its address
+// system-owned with no data
+fn is_orphaned(stake_account: &AccountInfo) -> bool {
+    stake_account.owner == &anchor_lang::system_program::ID && stake_account.data_is_empty()
+}
+
+// Same check the stake program's withdraw makes, surfaced with an actionable error
+// Shared by close_resolution, close_stakeless_resolution and can_close so the
+// preview can't drift from the real guards
+fn close_eligibility<'a, 'info: 'a>(
+    resolution: &ResolutionAccount,
+    now: i64,
+    stake_accounts: impl IntoIterator<Item = &'a AccountInfo<'info>>,
+    clock: &Clock,
+    stake_history: &StakeHistory,
+) -> Result<CloseEligibility> {
+    // If resolution is not yet approved, then it's not possible to close the
+    // resolution before the end time and the grace period after it have passed.
+    // Inside the grace period the lockup is over and only the approvals are missing
+    let is_approved = resolution.is_approved(now) || resolution.is_emergency_released();
+    if !is_approved && now < resolution.end_time {
+        return Ok(CloseEligibility::LockupInForce);
+    }
+    if !is_approved && now < resolution.approval_deadline() {
+        return Ok(CloseEligibility::NotEnoughApprovals);
+    }
+    // only the approved payout is disputed, a failed resolution closes as usual
+    if is_approved && resolution.is_flagged(now) {
+        return Ok(CloseEligibility::Flagged);
+    }
+
+    for stake_account in stake_accounts {
+        if !is_stake_inactive(stake_account, clock, stake_history)? {
+            return Ok(CloseEligibility::StakeNotDeactivated);
+        }
+    }
+
+    Ok(CloseEligibility::Ok)
+}
+
+fn is_stake_inactive(
+    stake_account: &AccountInfo,
+    clock: &Clock,
+    stake_history: &StakeHistory,
+) -> Result<bool> {
+    if let Some(stake) = stake_state(stake_account)?.stake() {
+        let staked = if clock.epoch >= stake.delegation.deactivation_epoch {
+            stake.delegation.stake(clock.epoch, stake_history, None)
+        } else {
+            stake.delegation.stake
+        };
+
+        return Ok(staked == 0);
+    }
+
+    Ok(true)
+}
+
+// Real delinquency is measured against the current epoch's credits, which the vote account
+// alone doesn't tell us. As a heuristic, a validator that has no vote lockouts or has never
+// rooted a slot isn't voting, so stake delegated to it would sit idle.
+fn ensure_validator_voting(vote_account: &AccountInfo) -> Result<()> {
+    let data = vote_account.try_borrow_data()?;
+    let vote_state =
+        VoteState::deserialize(&data).map_err(|_| ResolutionErrorCode::InvalidVoteAccount)?;
+
+    if vote_state.votes.is_empty() || vote_state.root_slot.is_none() {
+        return Err(ResolutionErrorCode::DelinquentValidator.into());
+    }
+
+    Ok(())
+}
+
+// Runs the checks Anchor would on a resolution passed outside the Accounts struct: owned
+// by this program with the right discriminator, writable, belonging to owner and living
+// at its canonical PDA
+fn load_owned_resolution<'info>(
+    account_info: &'info AccountInfo<'info>,
+    owner: &Pubkey,
+) -> Result<Account<'info, ResolutionAccount>> {
+    if !account_info.is_writable {
+        return Err(ResolutionErrorCode::InvalidResolutionAccount.into());
+    }
+
+    let resolution = Account::<ResolutionAccount>::try_from(account_info)?;
+    if resolution.owner != *owner {
+        return Err(ResolutionErrorCode::InvalidResolutionAccount.into());
+    }
+
+    let (address, bump) = Pubkey::find_program_address(
+        &[
+            b"resolution",
+            owner.as_ref(),
+            &resolution.nonce.to_le_bytes(),
+        ],
+        &crate::ID,
+    );
+    if address != account_info.key() {
+        return Err(ResolutionErrorCode::InvalidResolutionAccount.into());
+    }
+    resolution.ensure_bump(bump)?;
+
+    Ok(resolution)
+}
+
+fn ensure_commission_within(
+    vote_account: &AccountInfo,
+    max_commission_bps: Option<u16>,
+) -> Result<()> {
+    if let Some(max_commission_bps) = max_commission_bps {
+        let data = vote_account.try_borrow_data()?;
+        let vote_state =
+            VoteState::deserialize(&data).map_err(|_| ResolutionErrorCode::InvalidVoteAccount)?;
+
+        if vote_state.commission as u16 * BPS_PER_PERCENT > max_commission_bps {
+            msg!("Validator commission: {}%", vote_state.commission);
+            return Err(ResolutionErrorCode::CommissionTooHigh.into());
+        }
+    }
+
+    Ok(())
+}
+
+// Only a heuristic: it catches the owner's own keys as the vote account's withdraw authority,
+// but not a validator run through any other wallet, a changed authority or a node identity
+fn ensure_not_self_validator(vote_account: &AccountInfo, owner_keys: &[Pubkey]) -> Result<()> {
+    let data = vote_account.try_borrow_data()?;
+    let vote_state =
+        VoteState::deserialize(&data).map_err(|_| ResolutionErrorCode::InvalidVoteAccount)?;
+
+    if owner_keys.contains(&vote_state.authorized_withdrawer) {
+        return Err(ResolutionErrorCode::SelfValidatorNotAllowed.into());
+    }
+
+    Ok(())
+}
+
+// has_one only ties the key to the resolution, the stake state itself has to agree
+// that the resolution PDA is the staker before any CPI goes out on its behalf
+fn ensure_staked_by(stake_account: &AccountInfo, resolution: &Pubkey) -> Result<()> {
+    match stake_state(stake_account)?.meta() {
+        Some(meta) if meta.authorized.staker == *resolution => Ok(()),
+        _ => Err(ResolutionErrorCode::InvalidStakeAccount.into()),
+    }
+}
+
+// The lockup is created with epoch 0 so only the timestamp is in force; a non zero epoch
+// would keep the stake locked past end_time
+fn ensure_lockup_matches(end_time: i64, lockup: &Lockup) -> Result<()> {
+    if lockup.unix_timestamp != end_time || lockup.epoch != 0 {
+        return Err(ResolutionErrorCode::LockupMismatch.into());
+    }
+
+    Ok(())
+}
+
+// The stake program's own merge checks fail with an opaque error, so the common mismatches are
+// caught up front: differing authorities or lockups, and live delegations to two validators
+fn ensure_mergeable(destination: &StakeStateV2, source: &StakeStateV2) -> Result<()> {
+    let (destination_meta, source_meta) = match (destination.meta(), source.meta()) {
+        (Some(destination_meta), Some(source_meta)) => (destination_meta, source_meta),
+        _ => return Err(ResolutionErrorCode::InvalidStakeAccount.into()),
+    };
+
+    if destination_meta.authorized != source_meta.authorized
+        || destination_meta.lockup != source_meta.lockup
+    {
+        return Err(ResolutionErrorCode::StakeAccountsNotMergeable.into());
+    }
+
+    if let (Some(destination), Some(source)) = (destination.delegation(), source.delegation()) {
+        let both_delegated =
+            destination.deactivation_epoch == u64::MAX && source.deactivation_epoch == u64::MAX;
+        if both_delegated && destination.voter_pubkey != source.voter_pubkey {
+            return Err(ResolutionErrorCode::StakeAccountsNotMergeable.into());
+        }
+    }
+
+    Ok(())
+}
+
+fn stake_state(stake_account: &AccountInfo) -> Result<StakeStateV2> {
+    let data = stake_account.try_borrow_data()?;
+    StakeStateV2::deserialize(&mut &data[..])
+        .map_err(|_| ResolutionErrorCode::InvalidStakeAccount.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+    use anchor_lang::InstructionData;
+
+    #[test]
+    fn validate_approver_set_accepts_valid_set() {
+        let owner = Pubkey::new_unique();
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        assert!(validate_approver_set(&owner, &approvers).is_ok());
+    }
+
+    #[test]
+    fn validate_approver_set_rejects_empty_set() {
+        let owner = Pubkey::new_unique();
+
+        assert_eq!(
+            validate_approver_set(&owner, &[]).unwrap_err(),
+            ResolutionErrorCode::InvalidNumApprovers.into()
+        );
+    }
+
+    #[test]
+    fn validate_approver_set_rejects_too_many_approvers() {
+        let owner = Pubkey::new_unique();
+        let approvers: Vec<Pubkey> = (0..=MAX_APPROVERS).map(|_| Pubkey::new_unique()).collect();
+
+        assert_eq!(
+            validate_approver_set(&owner, &approvers).unwrap_err(),
+            ResolutionErrorCode::InvalidNumApprovers.into()
+        );
+    }
+
+    #[test]
+    fn validate_approver_set_rejects_owner() {
+        let owner = Pubkey::new_unique();
+        let approvers = [Pubkey::new_unique(), owner];
+
+        assert_eq!(
+            validate_approver_set(&owner, &approvers).unwrap_err(),
+            ResolutionErrorCode::InvalidApprover.into()
+        );
+    }
+
+    #[test]
+    fn validate_approver_set_rejects_duplicates() {
+        let owner = Pubkey::new_unique();
+        let approver = Pubkey::new_unique();
+        let approvers = [approver, Pubkey::new_unique(), approver];
+
+        assert_eq!(
+            validate_approver_set(&owner, &approvers).unwrap_err(),
+            ResolutionErrorCode::InvalidApprover.into()
+        );
+    }
+
+    #[test]
+    fn validate_approver_set_checks_each_set_size() {
+        let owner = Pubkey::new_unique();
+
+        for size in [1, 3, MAX_APPROVERS as usize] {
+            let approvers: Vec<Pubkey> = (0..size).map(|_| Pubkey::new_unique()).collect();
+            assert!(validate_approver_set(&owner, &approvers).is_ok());
+
+            let mut with_owner = approvers.clone();
+            with_owner[size - 1] = owner;
+            assert_eq!(
+                validate_approver_set(&owner, &with_owner).unwrap_err(),
+                ResolutionErrorCode::InvalidApprover.into()
+            );
+
+            // a single approver can't be duplicated without growing the set
+            if size > 1 {
+                let mut with_duplicate = approvers.clone();
+                with_duplicate[size - 1] = approvers[0];
+                assert_eq!(
+                    validate_approver_set(&owner, &with_duplicate).unwrap_err(),
+                    ResolutionErrorCode::InvalidApprover.into()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn validate_text_accepts_multibyte_text_up_to_the_byte_limit() {
+        // four bytes per emoji
+        let text = "🏃".repeat(MAX_TEXT_LEN as usize / 4);
+
+        assert!(validate_text(&text).is_ok());
+    }
+
+    #[test]
+    fn validate_text_rejects_multibyte_text_over_the_byte_limit() {
+        let text = "🏃".repeat(MAX_TEXT_LEN as usize / 4 + 1);
+
+        assert_eq!(
+            validate_text(&text).unwrap_err(),
+            ResolutionErrorCode::InvalidText.into()
+        );
+    }
+
+    #[test]
+    fn validate_text_rejects_blank_text() {
+        for text in ["", "   ", "\n\t"] {
+            assert_eq!(
+                validate_text(text).unwrap_err(),
+                ResolutionErrorCode::InvalidText.into()
+            );
+        }
+    }
+
+    #[test]
+    fn ensure_registered_accepts_registered_approvers() {
+        let registered = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+
+        assert!(ensure_registered(Some(&registered), &registered[..2]).is_ok());
+    }
+
+    #[test]
+    fn ensure_registered_rejects_unregistered_approver() {
+        let registered = [Pubkey::new_unique()];
+        let approvers = [registered[0], Pubkey::new_unique()];
+
+        assert_eq!(
+            ensure_registered(Some(&registered), &approvers).unwrap_err(),
+            ResolutionErrorCode::ApproverNotRegistered.into()
+        );
+    }
+
+    #[test]
+    fn ensure_registered_rejects_missing_registry() {
+        assert_eq!(
+            ensure_registered(None, &[Pubkey::new_unique()]).unwrap_err(),
+            ResolutionErrorCode::ApproverNotRegistered.into()
+        );
+    }
+
+    #[test]
+    fn ensure_not_approver_accepts_outside_payee() {
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        assert!(ensure_not_approver(&approvers, &Pubkey::new_unique()).is_ok());
+    }
+
+    #[test]
+    fn ensure_not_approver_rejects_withdraw_destination_overlap() {
+        let withdraw_destination = Pubkey::new_unique();
+        let approvers = [Pubkey::new_unique(), withdraw_destination];
+
+        assert_eq!(
+            ensure_not_approver(&approvers, &withdraw_destination).unwrap_err(),
+            ResolutionErrorCode::InvalidApprover.into()
+        );
+    }
+
+    #[test]
+    fn ensure_not_approver_rejects_recipient_overlap() {
+        let recipient = Pubkey::new_unique();
+        let approvers = [recipient];
+
+        assert_eq!(
+            ensure_not_approver(&approvers, &recipient).unwrap_err(),
+            ResolutionErrorCode::InvalidApprover.into()
+        );
+    }
+
+    // A zeroed buffer deserializes to empty vectors and strings, None and zeroes
+    fn empty_resolution() -> ResolutionAccount {
+        let data = vec![0u8; ResolutionAccount::INIT_SPACE];
+        ResolutionAccount::deserialize(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn can_approve_skips_outsiders_repeats_and_expired_resolutions() {
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut resolution = empty_resolution();
+        resolution.approvers = approvers.to_vec();
+        resolution.end_time = 1_000;
+        resolution.approved_by = vec![approvers[0]];
+        resolution.approved_at = vec![100];
+
+        assert!(resolution.can_approve(&approvers[1], 500));
+        assert!(!resolution.can_approve(&approvers[0], 500));
+        assert!(!resolution.can_approve(&Pubkey::new_unique(), 500));
+        // past the grace period nobody can approve anymore
+        assert!(!resolution.can_approve(&approvers[1], 1_000));
+
+        // a stale approval can be renewed
+        resolution.approval_validity = 300;
+        assert!(resolution.can_approve(&approvers[0], 500));
+    }
+
+    // Serialized resolution of owner at its canonical PDA, as batch_approve gets it
+    fn stored_resolution(owner: Pubkey, nonce: u64) -> (Pubkey, Vec<u8>) {
+        let (address, bump) = Pubkey::find_program_address(
+            &[b"resolution", owner.as_ref(), &nonce.to_le_bytes()],
+            &ID,
+        );
+        let mut resolution = empty_resolution();
+        resolution.owner = owner;
+        resolution.nonce = nonce;
+        resolution.bump = bump;
+
+        let mut data = Vec::new();
+        resolution.try_serialize(&mut data).unwrap();
+        (address, data)
+    }
+
+    fn load_resolution(
+        key: Pubkey,
+        is_writable: bool,
+        mut data: Vec<u8>,
+        program: Pubkey,
+        owner: &Pubkey,
+    ) -> Result<()> {
+        let mut lamports = 0;
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            is_writable,
+            &mut lamports,
+            &mut data,
+            &program,
+            false,
+            0,
+        );
+
+        load_owned_resolution(&account_info, owner).map(|_| ())
+    }
+
+    #[test]
+    fn load_owned_resolution_checks_owner_address_and_writability() {
+        let owner = Pubkey::new_unique();
+        let (address, data) = stored_resolution(owner, 7);
+
+        assert!(load_resolution(address, true, data.clone(), ID, &owner).is_ok());
+
+        for result in [
+            load_resolution(address, false, data.clone(), ID, &owner),
+            load_resolution(address, true, data.clone(), ID, &Pubkey::new_unique()),
+            load_resolution(Pubkey::new_unique(), true, data.clone(), ID, &owner),
+        ] {
+            assert_eq!(
+                result.unwrap_err(),
+                ResolutionErrorCode::InvalidResolutionAccount.into()
+            );
+        }
+
+        // a lookalike owned by another program never deserializes
+        assert!(load_resolution(address, true, data, Pubkey::new_unique(), &owner).is_err());
+    }
+
+    fn initialized_stake_data(staker: Pubkey) -> Vec<u8> {
+        let meta = stake::state::Meta {
+            authorized: Authorized {
+                staker,
+                withdrawer: Pubkey::new_unique(),
+            },
+            ..stake::state::Meta::default()
+        };
+
+        StakeStateV2::Initialized(meta).try_to_vec().unwrap()
+    }
+
+    fn delegated_stake_state(meta: stake::state::Meta, voter_pubkey: Pubkey) -> StakeStateV2 {
+        let stake = stake::state::Stake {
+            delegation: stake::state::Delegation {
+                voter_pubkey,
+                ..stake::state::Delegation::default()
+            },
+            ..stake::state::Stake::default()
+        };
+
+        StakeStateV2::Stake(meta, stake, stake::stake_flags::StakeFlags::empty())
+    }
+
+    #[test]
+    fn ensure_mergeable_accepts_matching_accounts() {
+        let meta = stake::state::Meta::default();
+        let voter = Pubkey::new_unique();
+
+        assert!(ensure_mergeable(
+            &delegated_stake_state(meta, voter),
+            &delegated_stake_state(meta, voter)
+        )
+        .is_ok());
+        assert!(ensure_mergeable(
+            &StakeStateV2::Initialized(meta),
+            &delegated_stake_state(meta, voter)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn ensure_mergeable_rejects_different_validators_or_authorities() {
+        let meta = stake::state::Meta::default();
+
+        assert_eq!(
+            ensure_mergeable(
+                &delegated_stake_state(meta, Pubkey::new_unique()),
+                &delegated_stake_state(meta, Pubkey::new_unique())
+            )
+            .unwrap_err(),
+            ResolutionErrorCode::StakeAccountsNotMergeable.into()
+        );
+
+        let other_meta = stake::state::Meta {
+            authorized: Authorized {
+                staker: Pubkey::new_unique(),
+                withdrawer: Pubkey::new_unique(),
+            },
+            ..meta
+        };
+        assert_eq!(
+            ensure_mergeable(
+                &StakeStateV2::Initialized(meta),
+                &StakeStateV2::Initialized(other_meta)
+            )
+            .unwrap_err(),
+            ResolutionErrorCode::StakeAccountsNotMergeable.into()
+        );
+    }
+
+    #[test]
+    fn ensure_staked_by_accepts_resolution_staker() {
+        let resolution = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = initialized_stake_data(resolution);
+        let stake_account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &stake::program::ID,
+            false,
+            0,
+        );
+
+        assert!(ensure_staked_by(&stake_account, &resolution).is_ok());
+    }
+
+    #[test]
+    fn ensure_staked_by_rejects_unrelated_stake_account() {
+        let resolution = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = initialized_stake_data(Pubkey::new_unique());
+        let stake_account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &stake::program::ID,
+            false,
+            0,
+        );
+
+        assert_eq!(
+            ensure_staked_by(&stake_account, &resolution).unwrap_err(),
+            ResolutionErrorCode::InvalidStakeAccount.into()
+        );
+    }
+
+    #[test]
+    fn stake_pool_deposit_sol_encodes_amount_and_signer() {
+        let keys: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+        let instruction = stake_pool::deposit_sol(
+            &keys[0], &keys[1], &keys[2], &keys[3], &keys[4], &keys[5], &keys[6], &keys[7],
+            &keys[8], 42,
+        );
+
+        assert_eq!(instruction.program_id, stake_pool::ID);
+        assert_eq!(instruction.data[0], 14);
+        assert_eq!(instruction.data[1..], 42u64.to_le_bytes());
+        assert_eq!(instruction.accounts.len(), 10);
+        let signers: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(signers, [keys[3]]);
+    }
+
+    #[test]
+    fn stake_pool_withdraw_sol_encodes_amount_and_signer() {
+        let keys: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+        let instruction = stake_pool::withdraw_sol(
+            &keys[0], &keys[1], &keys[2], &keys[3], &keys[4], &keys[5], &keys[6], &keys[7],
+            &keys[8], 7,
+        );
+
+        assert_eq!(instruction.program_id, stake_pool::ID);
+        assert_eq!(instruction.data[0], 16);
+        assert_eq!(instruction.data[1..], 7u64.to_le_bytes());
+        assert_eq!(instruction.accounts.len(), 12);
+        let signers: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(signers, [keys[2]]);
+    }
+
+    #[test]
+    fn ensure_lockup_matches_accepts_mirrored_end_time() {
+        let lockup = Lockup {
+            unix_timestamp: 1_700_000_000,
+            epoch: 0,
+            custodian: Pubkey::new_unique(),
+        };
+
+        assert!(ensure_lockup_matches(1_700_000_000, &lockup).is_ok());
+    }
+
+    #[test]
+    fn ensure_lockup_matches_rejects_drift() {
+        let custodian = Pubkey::new_unique();
+        for (unix_timestamp, epoch) in [(1_700_000_001, 0), (1_700_000_000, 5)] {
+            let lockup = Lockup {
+                unix_timestamp,
+                epoch,
+                custodian,
+            };
+
+            assert_eq!(
+                ensure_lockup_matches(1_700_000_000, &lockup).unwrap_err(),
+                ResolutionErrorCode::LockupMismatch.into()
+            );
+        }
+    }
+
+    #[test]
+    fn resolution_summary_has_no_padding() {
+        assert_eq!(ResolutionSummary::LEN, 51);
+    }
+
+    #[test]
+    fn resolution_summary_bytes_follow_the_documented_layout() {
+        let owner = Pubkey::new_unique();
+        let summary = ResolutionSummary {
+            version: RESOLUTION_SUMMARY_VERSION,
+            owner: owner.to_bytes(),
+            stake_amount: 5_000_000_000u64.to_le_bytes(),
+            end_time: 1_700_000_000i64.to_le_bytes(),
+            approval_count: 2,
+            status: ResolutionStatus::ActiveApproved as u8,
+        };
+
+        let bytes = summary.to_bytes();
+        assert_eq!(bytes[0], RESOLUTION_SUMMARY_VERSION);
+        assert_eq!(bytes[1..33], owner.to_bytes());
+        assert_eq!(bytes[33..41], 5_000_000_000u64.to_le_bytes());
+        assert_eq!(bytes[41..49], 1_700_000_000i64.to_le_bytes());
+        assert_eq!(bytes[49], 2);
+        assert_eq!(bytes[50], 1);
+    }
+
+    #[test]
+    fn validate_milestones_accepts_no_milestones() {
+        assert!(validate_milestones(&[], &[], 0, 100, 1_000).is_ok());
+    }
+
+    #[test]
+    fn validate_milestones_accepts_increasing_milestones_within_stake() {
+        assert!(validate_milestones(&[200, 500], &[10, 20], 30, 100, 1_000).is_ok());
+    }
+
+    #[test]
+    fn validate_milestones_rejects_invalid_milestones() {
+        let cases: [(&[i64], &[u64], u64); 7] = [
+            (&[200], &[10], 0),
+            (&[200, 500], &[10], 30),
+            (&[500, 200], &[10, 20], 30),
+            (&[100], &[10], 30),
+            (&[1_001], &[10], 30),
+            (&[200], &[0], 30),
+            (&[200, 500], &[10, 21], 30),
+        ];
+
+        for (milestones, milestone_releases, stake_amount) in cases {
+            assert_eq!(
+                validate_milestones(milestones, milestone_releases, stake_amount, 100, 1_000)
+                    .unwrap_err(),
+                ResolutionErrorCode::InvalidMilestones.into()
+            );
+        }
+    }
+
+    // Runs append_details through the program entrypoint against a resolution at its
+    // canonical address, with `stored_bump` as the bump it recorded at init
+    fn append_details_with_bump(owner: Pubkey, stored_bump: u8) -> ProgramResult {
+        let (address, data) = stored_resolution(owner, 3);
+        let mut resolution = ResolutionAccount::try_deserialize(&mut data.as_slice())?;
+        resolution.bump = stored_bump;
+        let mut resolution_data = Vec::new();
+        resolution.try_serialize(&mut resolution_data)?;
+
+        let (details_address, details_bump) =
+            Pubkey::find_program_address(&[b"details", address.as_ref()], &ID);
+        let mut details_data = Vec::new();
+        ResolutionDetails {
+            resolution: address,
+            text: String::new(),
+            bump: details_bump,
+        }
+        .try_serialize(&mut details_data)?;
+
+        let system_program = System::id();
+        let (mut owner_lamports, mut owner_data) = (0, Vec::new());
+        let (mut resolution_lamports, mut details_lamports) = (0, 0);
+        let accounts = [
+            AccountInfo::new(
+                &owner,
+                true,
+                false,
+                &mut owner_lamports,
+                &mut owner_data,
+                &system_program,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &address,
+                false,
+                false,
+                &mut resolution_lamports,
+                &mut resolution_data,
+                &ID,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &details_address,
+                false,
+                true,
+                &mut details_lamports,
+                &mut details_data,
+                &ID,
+                false,
+                0,
+            ),
+        ];
+        let data = crate::instruction::AppendDetails {
+            text: "more".to_string(),
+        }
+        .data();
+
+        entry(&ID, &accounts, &data)
+    }
+
+    // InitSpace only sees the max_len attributes, so a resolution filled up to every
+    // constant has to serialize to exactly the space allocated for it
+    #[test]
+    fn init_space_matches_a_resolution_at_every_limit() {
+        let key = Pubkey::new_unique();
+        let approvers = vec![key; MAX_APPROVERS as usize];
+        let mut resolution = empty_resolution();
+        resolution.title = "a".repeat(MAX_TITLE_LEN as usize);
+        resolution.text = "a".repeat(MAX_TEXT_LEN as usize);
+        resolution.approvers = approvers.clone();
+        resolution.approved_by = approvers.clone();
+        resolution.approval_notes = vec!["a".repeat(MAX_NOTE_LEN as usize); MAX_APPROVERS as usize];
+        resolution.approved_at = vec![0; MAX_APPROVERS as usize];
+        resolution.approval_delegates = vec![
+            ApprovalDelegate {
+                approver: key,
+                delegate: key,
+            };
+            MAX_APPROVERS as usize
+        ];
+        resolution.extension_approved_by = approvers.clone();
+        resolution.emergency_release_approved_by = approvers.clone();
+        resolution.pending_removal = Some(key);
+        resolution.removal_consented_by = approvers.clone();
+        resolution.milestones = vec![0; MAX_MILESTONES as usize];
+        resolution.milestone_releases = vec![0; MAX_MILESTONES as usize];
+        resolution.milestone_approved_by = approvers;
+        resolution.max_commission_bps = Some(0);
+        resolution.custodian = Some(key);
+        resolution.second_stake_account = Some(key);
+        resolution.stake_pool = Some(key);
+        resolution.pool_token_account = Some(key);
+        resolution.weights = vec![1; MAX_APPROVERS as usize];
+        resolution.flaggers = vec![key; MAX_APPROVERS as usize];
+
+        assert_eq!(
+            resolution.try_to_vec().unwrap().len(),
+            ResolutionAccount::INIT_SPACE
+        );
+    }
+
+    #[test]
+    fn init_space_matches_full_details_and_registry() {
+        let details = ResolutionDetails {
+            resolution: Pubkey::new_unique(),
+            text: "a".repeat(MAX_DETAILS_LEN as usize),
+            bump: 0,
+        };
+        assert_eq!(
+            details.try_to_vec().unwrap().len(),
+            ResolutionDetails::INIT_SPACE
+        );
+
+        let registry = Registry {
+            authority: Pubkey::new_unique(),
+            approvers: vec![Pubkey::new_unique(); MAX_REGISTRY_SIZE as usize],
+            bump: 0,
+        };
+        assert_eq!(registry.try_to_vec().unwrap().len(), Registry::INIT_SPACE);
+
+        let approver_programs = ApproverPrograms {
+            authority: Pubkey::new_unique(),
+            programs: vec![Pubkey::new_unique(); MAX_APPROVER_PROGRAMS as usize],
+            bump: 0,
+        };
+        assert_eq!(
+            approver_programs.try_to_vec().unwrap().len(),
+            ApproverPrograms::INIT_SPACE
+        );
+    }
+
+    #[test]
+    fn append_details_rejects_a_stored_bump_that_isnt_canonical() {
+        let bump_mismatch: ProgramError = Error::from(ResolutionErrorCode::BumpMismatch).into();
+        let owner = Pubkey::new_unique();
+        let (_, canonical) = Pubkey::find_program_address(
+            &[b"resolution", owner.as_ref(), &3u64.to_le_bytes()],
+            &ID,
+        );
+
+        for bump in [0, canonical.wrapping_sub(1), 255] {
+            if bump == canonical {
+                continue;
+            }
+            // the address and every account constraint still check out, only the bump is off
+            assert_eq!(
+                append_details_with_bump(owner, bump),
+                Err(bump_mismatch.clone())
+            );
+        }
+
+        // with the canonical bump the handler gets past the check, on to reading the clock
+        assert_ne!(
+            append_details_with_bump(owner, canonical),
+            Err(bump_mismatch)
+        );
+    }
+
+    // Byte for byte what the first release wrote, built by hand rather than through
+    // LegacyResolutionAccount so the test doesn't just mirror its derive
+    fn baseline_resolution_data(
+        owner: &Pubkey,
+        approvers: &[Pubkey],
+        approved_by: &[Pubkey],
+        stake_account: &Pubkey,
+    ) -> Vec<u8> {
+        let mut data = ResolutionAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(b"Gym");
+        data.push(b'!');
+        for keys in [approvers, approved_by] {
+            data.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+            for key in keys {
+                data.extend_from_slice(key.as_ref());
+            }
+        }
+        data.extend_from_slice(&5_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(stake_account.as_ref());
+        data.extend_from_slice(&100i64.to_le_bytes());
+        data.extend_from_slice(&200i64.to_le_bytes());
+        data.push(254);
+        // the first release allocated for its max_len limits, the rest stays zeroed
+        data.resize(557, 0);
+        data
+    }
+
+    #[test]
+    fn legacy_resolution_decodes_baseline_bytes() {
+        let owner = Pubkey::new_unique();
+        let approvers = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let stake_account = Pubkey::new_unique();
+        let data = baseline_resolution_data(&owner, &approvers, &approvers[..1], &stake_account);
+        assert_eq!(data.len(), LegacyResolutionAccount::LEN);
+
+        let legacy = LegacyResolutionAccount::try_from_account_data(&data).unwrap();
+        assert_eq!(
+            legacy,
+            LegacyResolutionAccount {
+                owner,
+                text: "Gym!".to_string(),
+                approvers: approvers.to_vec(),
+                approved_by: approvers[..1].to_vec(),
+                stake_amount: 5_000_000_000,
+                stake_account,
+                start_time: 100,
+                end_time: 200,
+                bump: 254,
+            }
+        );
+    }
+
+    #[test]
+    fn legacy_resolution_rejects_current_layout_and_foreign_data() {
+        let owner = Pubkey::new_unique();
+        let data = baseline_resolution_data(&owner, &[], &[], &Pubkey::new_unique());
+
+        let mut current = Vec::new();
+        empty_resolution().try_serialize(&mut current).unwrap();
+        let mut foreign = data.clone();
+        foreign[0] ^= 1;
+
+        for data in [&current[..], &foreign[..], &data[..data.len() - 1]] {
+            assert_eq!(
+                LegacyResolutionAccount::try_from_account_data(data).unwrap_err(),
+                ResolutionErrorCode::InvalidLegacyResolution.into()
+            );
+        }
+    }
+
+    fn program_instruction(data: Vec<u8>, account: Pubkey) -> Instruction {
+        Instruction {
+            program_id: crate::ID,
+            accounts: vec![AccountMeta::new(account, false)],
+            data,
+        }
+    }
+
+    #[test]
+    fn deactivates_resolution_matches_deactivations_of_the_same_resolution() {
+        let resolution = Pubkey::new_unique();
+        let deactivate = crate::instruction::DeactivateResolutionStake::DISCRIMINATOR.to_vec();
+        let initiate_close = crate::instruction::InitiateClose::DISCRIMINATOR.to_vec();
+
+        assert!(deactivates_resolution(
+            &program_instruction(deactivate.clone(), resolution),
+            &resolution
+        ));
+        assert!(deactivates_resolution(
+            &program_instruction(initiate_close, resolution),
+            &resolution
+        ));
+        // another resolution's deactivation doesn't get in the way
+        assert!(!deactivates_resolution(
+            &program_instruction(deactivate, Pubkey::new_unique()),
+            &resolution
+        ));
+    }
+
+    #[test]
+    fn deactivates_resolution_ignores_other_instructions() {
+        let resolution = Pubkey::new_unique();
+        let close = crate::instruction::CloseResolution::DISCRIMINATOR.to_vec();
+        let mut foreign = program_instruction(
+            crate::instruction::DeactivateResolutionStake::DISCRIMINATOR.to_vec(),
+            resolution,
+        );
+        foreign.program_id = Pubkey::new_unique();
+
+        assert!(!deactivates_resolution(
+            &program_instruction(close, resolution),
+            &resolution
+        ));
+        assert!(!deactivates_resolution(&foreign, &resolution));
+    }
+
+    #[test]
+    fn weighted_threshold_sums_approver_weights() {
+        let coach = Pubkey::new_unique();
+        let friends = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![coach, friends[0], friends[1]];
+        resolution.weights = vec![60, 20, 20];
+        resolution.required_approvals = 60;
+
+        // both friends together still fall short of the coach alone
+        resolution.approved_by = friends.to_vec();
+        resolution.approved_at = vec![0, 0];
+        assert_eq!(resolution.valid_approval_weight(0), 40);
+        assert!(!resolution.is_approved(0));
+
+        resolution.approved_by = vec![coach];
+        resolution.approved_at = vec![0];
+        assert!(resolution.is_approved(0));
+    }
+
+    #[test]
+    fn approval_progress_at_none_partial_and_full() {
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut resolution = empty_resolution();
+        resolution.approvers = approvers.to_vec();
+        resolution.required_approvals = 2;
+
+        assert_eq!(
+            resolution.approval_progress(0),
+            ApprovalProgress {
+                approved: 0,
+                required: 2,
+                threshold_met: false,
+            }
+        );
+
+        resolution.approved_by = vec![approvers[0]];
+        resolution.approved_at = vec![0];
+        let partial = resolution.approval_progress(0);
+        assert_eq!(partial.approved, 1);
+        assert!(!partial.threshold_met);
+
+        resolution.approved_by = approvers.to_vec();
+        resolution.approved_at = vec![0, 0];
+        assert_eq!(
+            resolution.approval_progress(0),
+            ApprovalProgress {
+                approved: 2,
+                required: 2,
+                threshold_met: true,
+            }
+        );
+        assert!(resolution.is_approved(0));
+    }
+
+    #[test]
+    fn validate_threshold_checks_weights() {
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        assert!(validate_threshold(&approvers, &[], 2).is_ok());
+        assert!(validate_threshold(&approvers, &[70, 30], 70).is_ok());
+        assert!(validate_threshold(&approvers, &[], 3).is_err());
+        // wrong length, a zero weight, not adding up to the total
+        for weights in [&[100][..], &[100, 0], &[50, 30]] {
+            assert_eq!(
+                validate_threshold(&approvers, weights, 50).unwrap_err(),
+                ResolutionErrorCode::InvalidWeights.into()
+            );
+        }
+        assert_eq!(
+            validate_threshold(&approvers, &[70, 30], TOTAL_APPROVAL_WEIGHT + 1).unwrap_err(),
+            ResolutionErrorCode::InvalidThreshold.into()
+        );
+    }
+
+    #[test]
+    fn stale_approvals_dont_count() {
+        let mut resolution = empty_resolution();
+        resolution.approved_by = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        resolution.approved_at = vec![100, 1_000];
+        resolution.required_approvals = 2;
+        resolution.approval_validity = 500;
+
+        assert!(resolution.is_approved(600));
+        // the first approval is older than approval_validity from here on
+        assert_eq!(resolution.valid_approval_count(601), 1);
+        assert!(!resolution.is_approved(601));
+        assert_eq!(resolution.valid_approval_count(1_501), 0);
+    }
+
+    #[test]
+    fn only_valid_approvals_make_idempotent_approval_a_no_op() {
+        let approver = Pubkey::new_unique();
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![approver];
+        resolution.approved_by = vec![approver];
+        resolution.approved_at = vec![100];
+        resolution.approval_validity = 500;
+        resolution.end_time = 10_000;
+
+        assert!(resolution.has_valid_approval(&approver, 600));
+        assert!(!resolution.can_approve(&approver, 600));
+
+        // stale from here on, so approve_resolution_idempotent renews it instead
+        assert!(!resolution.has_valid_approval(&approver, 601));
+        assert!(resolution.can_approve(&approver, 601));
+
+        assert!(!resolution.has_valid_approval(&Pubkey::new_unique(), 600));
+    }
+
+    #[test]
+    fn approvals_never_expire_without_validity() {
+        let mut resolution = empty_resolution();
+        resolution.approved_by = vec![Pubkey::new_unique()];
+        resolution.approved_at = vec![100];
+        resolution.required_approvals = 1;
+
+        assert!(resolution.is_approved(i64::MAX));
+    }
+
+    #[test]
+    fn every_approver_flags_only_once() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![a, b];
+
+        resolution.record_flag(a, 0).unwrap();
+        // a second flag can only come in once the first lapsed
+        assert_eq!(
+            resolution.record_flag(b, DISPUTE_WINDOW - 1).unwrap_err(),
+            ResolutionErrorCode::AlreadyFlagged.into()
+        );
+        resolution.record_flag(b, DISPUTE_WINDOW).unwrap();
+        assert_eq!(resolution.flagged_by, b);
+
+        // a can't take another turn after b's flag lapses
+        assert_eq!(
+            resolution.record_flag(a, 2 * DISPUTE_WINDOW).unwrap_err(),
+            ResolutionErrorCode::AlreadyFlagged.into()
+        );
+        assert!(!resolution.is_flagged(2 * DISPUTE_WINDOW));
+        assert_eq!(resolution.flaggers, vec![a, b]);
+
+        assert_eq!(
+            resolution
+                .record_flag(Pubkey::new_unique(), 2 * DISPUTE_WINDOW)
+                .unwrap_err(),
+            ResolutionErrorCode::InvalidApprover.into()
+        );
+    }
+
+    #[test]
+    fn flag_lapses_after_the_dispute_window() {
+        let mut resolution = empty_resolution();
+        resolution.flagged_at = 1_000;
+        assert!(!resolution.is_flagged(1_000));
+
+        resolution.flagged = true;
+        assert!(resolution.is_flagged(1_000));
+        assert!(resolution.is_flagged(1_000 + DISPUTE_WINDOW - 1));
+        assert!(!resolution.is_flagged(1_000 + DISPUTE_WINDOW));
+    }
+
+    #[test]
+    fn flagged_approved_resolution_cant_withdraw() {
+        let approver = Pubkey::new_unique();
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![approver, Pubkey::new_unique()];
+        resolution.approved_by = vec![approver];
+        resolution.approved_at = vec![0];
+        resolution.required_approvals = 1;
+
+        resolution
+            .record_flag(resolution.approvers[1], 1_000)
+            .unwrap();
+
+        // still approved, the flag alone holds the payout back
+        assert!(resolution.is_approved(1_000));
+        assert_eq!(
+            resolution.ensure_not_flagged(1_000).unwrap_err(),
+            ResolutionErrorCode::ResolutionFlagged.into()
+        );
+        assert!(resolution
+            .ensure_not_flagged(1_000 + DISPUTE_WINDOW)
+            .is_ok());
+    }
+
+    #[test]
+    fn owner_resolves_a_flag_by_asking_for_approval_again() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![a, b];
+        resolution.approved_by = vec![a];
+        resolution.approval_notes = vec!["done".to_string()];
+        resolution.approved_at = vec![0];
+        resolution.milestone_approved_by = vec![a];
+        resolution.required_approvals = 1;
+
+        assert_eq!(
+            resolution.resolve_flag(1_000).unwrap_err(),
+            ResolutionErrorCode::NotFlagged.into()
+        );
+
+        resolution.record_flag(b, 1_000).unwrap();
+        resolution.resolve_flag(1_000).unwrap();
+
+        assert!(!resolution.is_flagged(1_000));
+        assert!(!resolution.is_approved(1_000));
+        assert!(resolution.approval_notes.is_empty());
+        assert!(resolution.approved_at.is_empty());
+        assert!(resolution.milestone_approved_by.is_empty());
+        assert_eq!(resolution.approval_nonce, 1);
+        // b already had their one flag
+        assert_eq!(
+            resolution.record_flag(b, 1_001).unwrap_err(),
+            ResolutionErrorCode::AlreadyFlagged.into()
+        );
+    }
+
+    #[test]
+    fn pick_approvers_draws_distinct_candidates() {
+        let candidates: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+
+        for seed in [[0u8; 32], [7u8; 32], [255u8; 32]] {
+            let picked = pick_approvers(&candidates, 4, seed);
+
+            assert_eq!(picked.len(), 4);
+            assert!(picked.iter().all(|approver| candidates.contains(approver)));
+            assert_eq!(picked.iter().collect::<BTreeSet<_>>().len(), 4);
+        }
+    }
+
+    #[test]
+    fn pick_approvers_is_deterministic_per_seed() {
+        let candidates: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+
+        assert_eq!(
+            pick_approvers(&candidates, 3, [1u8; 32]),
+            pick_approvers(&candidates, 3, [1u8; 32])
+        );
+        // the whole pool comes back when every candidate is drawn
+        assert_eq!(
+            pick_approvers(&candidates, 10, [1u8; 32])
+                .iter()
+                .collect::<BTreeSet<_>>(),
+            candidates.iter().collect::<BTreeSet<_>>()
+        );
+    }
+
+    #[test]
+    fn from_legacy_keeps_the_first_release_behaviour() {
+        let owner = Pubkey::new_unique();
+        let approvers = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let stake_account = Pubkey::new_unique();
+        let vote_account = Pubkey::new_unique();
+        let data = baseline_resolution_data(&owner, &approvers, &approvers[1..], &stake_account);
+        let legacy = LegacyResolutionAccount::try_from_account_data(&data).unwrap();
+        let clock = Clock {
+            slot: 9,
+            unix_timestamp: 150,
+            ..Clock::default()
+        };
+
+        let resolution = ResolutionAccount::from_legacy(legacy, 3, 251, 42, vote_account, &clock);
+
+        assert_eq!(resolution.owner, owner);
+        assert_eq!(resolution.nonce, 3);
+        assert_eq!(resolution.bump, 251);
+        assert_eq!(resolution.id, 42);
+        assert_eq!(resolution.text, "Gym!");
+        assert_eq!(resolution.required_approvals, 3);
+        assert_eq!(resolution.approved_by, approvers[1..].to_vec());
+        assert_eq!(resolution.approved_at, vec![150, 150]);
+        assert_eq!(resolution.approval_notes.len(), 2);
+        assert_eq!(resolution.withdraw_destination, owner);
+        assert_eq!(resolution.stake_account, stake_account);
+        assert_eq!(resolution.validator_vote_account, vote_account);
+        assert_eq!((resolution.start_time, resolution.end_time), (100, 200));
+        assert_eq!(resolution.penalty_bps, 0);
+        assert!(!resolution.is_approved(150));
+
+        // and it fits the space init allocates
+        assert!(resolution.try_to_vec().unwrap().len() <= ResolutionAccount::INIT_SPACE);
+    }
+
+    #[test]
+    fn unlocked_amount_is_linear_over_the_duration() {
+        assert_eq!(unlocked_amount(1_000, 100, 400, 100), 0);
+        assert_eq!(unlocked_amount(1_000, 100, 400, 200), 250);
+        assert_eq!(unlocked_amount(1_000, 100, 400, 499), 997);
+        assert_eq!(unlocked_amount(1_000, 100, 400, 500), 1_000);
+    }
+
+    #[test]
+    fn unlocked_amount_clamps_outside_the_schedule() {
+        assert_eq!(unlocked_amount(1_000, 100, 400, 50), 0);
+        assert_eq!(unlocked_amount(1_000, 100, 400, i64::MAX), 1_000);
+        assert_eq!(
+            unlocked_amount(u64::MAX, 0, MAX_VAULT_DURATION, MAX_VAULT_DURATION / 2),
+            u64::MAX / 2
+        );
+    }
+
+    #[test]
+    fn accrued_rewards_is_the_surplus_over_rent_and_principal() {
+        assert_eq!(
+            accrued_rewards(2_282_880 + 5_000_000_000 + 1_234, 2_282_880, 5_000_000_000),
+            1_234
+        );
+        assert_eq!(
+            accrued_rewards(2_282_880 + 5_000_000_000, 2_282_880, 5_000_000_000),
+            0
+        );
+    }
+
+    #[test]
+    fn accrued_rewards_clamps_a_short_balance_to_zero() {
+        assert_eq!(
+            accrued_rewards(2_282_880 + 1_000, 2_282_880, 5_000_000_000),
+            0
+        );
+        assert_eq!(accrued_rewards(0, 2_282_880, 5_000_000_000), 0);
+    }
+
+    #[test]
+    fn approver_for_maps_a_delegate_to_its_approver() {
+        let approver = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![approver];
+        resolution.approval_delegates = vec![ApprovalDelegate { approver, delegate }];
+
+        assert_eq!(resolution.approver_for(&approver), approver);
+        assert_eq!(resolution.approver_for(&delegate), approver);
+    }
+
+    #[test]
+    fn approver_for_passes_unknown_signers_through() {
+        let stranger = Pubkey::new_unique();
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![Pubkey::new_unique()];
+
+        // record_approval then rejects it with InvalidApprover
+        assert_eq!(resolution.approver_for(&stranger), stranger);
+    }
+
+    #[test]
+    fn ensure_bump_rejects_a_different_bump() {
+        let mut resolution = empty_resolution();
+        resolution.bump = 255;
+
+        assert_eq!(
+            resolution.ensure_bump(254).unwrap_err(),
+            ResolutionErrorCode::BumpMismatch.into()
+        );
+    }
+
+    // Same layout the Ed25519 program's new_ed25519_instruction produces, with a dummy signature
+    fn ed25519_instruction_data(public_key: &[u8; 32], message: &[u8], index: u16) -> Vec<u8> {
+        let public_key_offset: u16 = 16;
+        let signature_offset: u16 = public_key_offset + 32;
+        let message_offset: u16 = signature_offset + 64;
+
+        let mut data = vec![1, 0];
+        for value in [
+            signature_offset,
+            index,
+            public_key_offset,
+            index,
+            message_offset,
+            message.len() as u16,
+            index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(public_key);
+        data.extend_from_slice(&[0; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn ed25519_signed_message_reads_key_and_message() {
+        let approver = Pubkey::new_unique();
+        let message = approval_message(&Pubkey::new_unique(), 3, 0, "done");
+        let data = ed25519_instruction_data(&approver.to_bytes(), &message, u16::MAX);
+
+        let (public_key, signed_message) = ed25519_signed_message(&data).unwrap();
+        assert_eq!(public_key, approver.as_ref());
+        assert_eq!(signed_message, &message[..]);
+    }
+
+    #[test]
+    fn ed25519_signed_message_rejects_data_from_other_instructions() {
+        let approver = Pubkey::new_unique();
+        let data = ed25519_instruction_data(&approver.to_bytes(), b"message", 0);
+
+        assert_eq!(
+            ed25519_signed_message(&data).unwrap_err(),
+            ResolutionErrorCode::InvalidResolutionSignature.into()
+        );
+    }
+
+    #[test]
+    fn ed25519_signed_message_rejects_multiple_signatures() {
+        let approver = Pubkey::new_unique();
+        let mut data = ed25519_instruction_data(&approver.to_bytes(), b"message", u16::MAX);
+        data[0] = 2;
+
+        assert_eq!(
+            ed25519_signed_message(&data).unwrap_err(),
+            ResolutionErrorCode::InvalidResolutionSignature.into()
+        );
+    }
+
+    #[test]
+    fn approval_message_binds_resolution_nonce_approval_and_note() {
+        let resolution = Pubkey::new_unique();
+        let message = approval_message(&resolution, 7, 100, "done");
+
+        assert_eq!(message[..32], resolution.to_bytes());
+        assert_eq!(message[32..40], 7u64.to_le_bytes());
+        assert_eq!(message[40..48], 100i64.to_le_bytes());
+        assert_eq!(&message[48..], b"done");
+        assert_ne!(message, approval_message(&resolution, 8, 100, "done"));
+    }
+
+    #[test]
+    fn signature_for_a_first_approval_doesnt_renew_it() {
+        let approver = Pubkey::new_unique();
+        let mut resolution = empty_resolution();
+        resolution.approvers = vec![approver];
+        resolution.end_time = 10_000;
+        resolution.approval_validity = 60;
+
+        let signed = approval_message(
+            &Pubkey::default(),
+            resolution.approval_nonce,
+            resolution.approved_at_of(&approver),
+            "done",
+        );
+
+        // once recorded and gone stale, the same signature no longer matches what
+        // approve_with_signature expects for a renewal
+        resolution.approved_by = vec![approver];
+        resolution.approved_at = vec![1_000];
+        assert!(resolution.can_approve(&approver, 1_061));
+        assert_ne!(
+            signed,
+            approval_message(
+                &Pubkey::default(),
+                resolution.approval_nonce,
+                resolution.approved_at_of(&approver),
+                "done",
+            )
+        );
+    }
 }
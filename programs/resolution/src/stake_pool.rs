@@ -0,0 +1,91 @@
+//! Instruction builders for the SPL stake pool program.
+//!
+//! Only the two SOL instructions are needed, so they're encoded by hand instead of
+//! pulling in the spl-stake-pool crate. The layout follows `StakePoolInstruction`:
+//! a one byte variant index followed by the borsh encoded arguments.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    stake, system_program, sysvar,
+};
+
+declare_id!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+
+const DEPOSIT_SOL: u8 = 14;
+const WITHDRAW_SOL: u8 = 16;
+
+fn instruction_data(variant: u8, amount: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(9);
+    data.push(variant);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+/// Deposits `lamports` from `lamports_from` into the pool reserve and mints pool tokens
+/// to `pool_tokens_to`
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_sol(
+    stake_pool: &Pubkey,
+    withdraw_authority: &Pubkey,
+    reserve_stake: &Pubkey,
+    lamports_from: &Pubkey,
+    pool_tokens_to: &Pubkey,
+    manager_fee_account: &Pubkey,
+    referrer_pool_tokens_account: &Pubkey,
+    pool_mint: &Pubkey,
+    token_program: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*stake_pool, false),
+            AccountMeta::new_readonly(*withdraw_authority, false),
+            AccountMeta::new(*reserve_stake, false),
+            AccountMeta::new(*lamports_from, true),
+            AccountMeta::new(*pool_tokens_to, false),
+            AccountMeta::new(*manager_fee_account, false),
+            AccountMeta::new(*referrer_pool_tokens_account, false),
+            AccountMeta::new(*pool_mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: instruction_data(DEPOSIT_SOL, lamports),
+    }
+}
+
+/// Burns `pool_tokens` from `pool_tokens_from` and withdraws their SOL value from the
+/// pool reserve to `lamports_to`
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_sol(
+    stake_pool: &Pubkey,
+    withdraw_authority: &Pubkey,
+    user_transfer_authority: &Pubkey,
+    pool_tokens_from: &Pubkey,
+    reserve_stake: &Pubkey,
+    lamports_to: &Pubkey,
+    manager_fee_account: &Pubkey,
+    pool_mint: &Pubkey,
+    token_program: &Pubkey,
+    pool_tokens: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(*stake_pool, false),
+            AccountMeta::new_readonly(*withdraw_authority, false),
+            AccountMeta::new_readonly(*user_transfer_authority, true),
+            AccountMeta::new(*pool_tokens_from, false),
+            AccountMeta::new(*reserve_stake, false),
+            AccountMeta::new(*lamports_to, false),
+            AccountMeta::new(*manager_fee_account, false),
+            AccountMeta::new(*pool_mint, false),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(sysvar::stake_history::ID, false),
+            AccountMeta::new_readonly(stake::program::ID, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: instruction_data(WITHDRAW_SOL, pool_tokens),
+    }
+}
@@ -3,7 +3,7 @@ import { AnchorError, Program } from "@coral-xyz/anchor";
 import { Resolution } from "../target/types/resolution";
 import { BankrunProvider, startAnchor } from "anchor-bankrun";
 import { BanksClient, Clock, ProgramTestContext } from "solana-bankrun";
-import { Keypair, PublicKey, VoteProgram } from "@solana/web3.js";
+import { ComputeBudgetProgram, Keypair, PublicKey, VoteProgram } from "@solana/web3.js";
 import * as voteAccount from './vote_account.json';
 import { assert, expect } from "chai";
 
@@ -29,16 +29,26 @@ describe("resolution", async () => {
     banksClient: BanksClient,
     program: Program<Resolution>,
     voteAccountPubkey: PublicKey,
+    delinquentVoteAccountPubkey: PublicKey,
     stakeKeypair: Keypair,
     approverA: Keypair,
     approverB: Keypair,
     approverC: Keypair,
+    charity: Keypair,
     resolutionPDA: PublicKey,
+    secondStakeKeypair: Keypair,
+    secondResolutionPDA: PublicKey,
+    maxLockupStakeKeypair: Keypair,
+    maxLockupResolutionPDA: PublicKey,
+    typedStakeKeypair: Keypair,
+    typedResolutionPDA: PublicKey,
+    statsPDA: PublicKey,
     resolutionAccountRent: bigint,
     stakeAccountRent: bigint;
 
   let incineratorPubkey = new PublicKey("1nc1nerator11111111111111111111111111111111")
   let stakeAmount = 5_000_000_000n;
+  let addedStake = 1_000_000_000n;
   let txFees = 5000n;
 
   before(async function () {
@@ -47,6 +57,13 @@ describe("resolution", async () => {
       c.charCodeAt(0),
     );
 
+    // Same vote account with its 31 lockouts dropped, so it looks like it stopped voting
+    delinquentVoteAccountPubkey = Keypair.generate().publicKey;
+    const votesOffset = 4 + 32 + 32 + 1;
+    const delinquentVoteAccountData = new Uint8Array(voteAccountData.length);
+    delinquentVoteAccountData.set(voteAccountData.subarray(0, votesOffset));
+    delinquentVoteAccountData.set(voteAccountData.subarray(votesOffset + 8 + 31 * 12), votesOffset + 8);
+
     context = await startAnchor("./", [],[
       {
         address: voteAccountPubkey,
@@ -57,6 +74,15 @@ describe("resolution", async () => {
           executable: false,
         },
       },
+      {
+        address: delinquentVoteAccountPubkey,
+        info: {
+          lamports: voteAccount.account.lamports,
+          data: delinquentVoteAccountData,
+          owner: VoteProgram.programId,
+          executable: false,
+        },
+      },
     ],);
     banksClient = context.banksClient;
     provider = new BankrunProvider(context);
@@ -66,16 +92,29 @@ describe("resolution", async () => {
     approverA = new Keypair();
     approverB = new Keypair();
     approverC = new Keypair();
+    charity = new Keypair();
     stakeKeypair = Keypair.generate();
 
     const rent = await banksClient.getRent()
-    resolutionAccountRent = rent.minimumBalance(557n);
+    resolutionAccountRent = rent.minimumBalance(2942n);
     stakeAccountRent = rent.minimumBalance(200n);
 
+    await program.methods.initializeConfig().accounts({
+      authority: payer.publicKey,
+    }).rpc();
+
+    await program.methods.initializeRegistry().accounts({
+      authority: payer.publicKey,
+    }).rpc();
+
+    await program.methods.initializeStats().accounts({
+      payer: payer.publicKey,
+    }).rpc();
+    [statsPDA,] = PublicKey.findProgramAddressSync([Buffer.from("stats")], program.programId);
   });
 
   it("initialize resolution", async () => {
-    await program.methods.initializeResolution(new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Hello World").accounts({
+    await program.methods.initializeResolution(new anchor.BN(0), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Hello World", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
       owner: payer.publicKey,
       stakeAccount: stakeKeypair.publicKey,
       validatorVoteAccount: voteAccountPubkey,
@@ -99,26 +138,92 @@ describe("resolution", async () => {
       },
     ]).signers([stakeKeypair]).rpc();
 
-     [resolutionPDA,] = await PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer()], program.programId);
+     [resolutionPDA,] = await PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)], program.programId);
     
 
     const resolutionAccount = await program.account.resolutionAccount.fetch(resolutionPDA);
 
     expect(resolutionAccount.owner.toString()).to.equal(payer.publicKey.toString());
-    expect(resolutionAccount.text).to.equal("Hello World");
+    expect(resolutionAccount.text).to.equal("Hello World", 3);
     expect(resolutionAccount.approvers.length).to.equal(3);
     expect(resolutionAccount.approvers[0].toString()).to.equal(approverA.publicKey.toString());
     expect(resolutionAccount.approvers[1].toString()).to.equal(approverB.publicKey.toString());
     expect(resolutionAccount.approvers[2].toString()).to.equal(approverC.publicKey.toString());
     expect(resolutionAccount.approvedBy.length).to.equal(0);
+    expect(resolutionAccount.requiredApprovals).to.equal(3);
     expect(resolutionAccount.stakeAmount.toNumber()).to.equal(5_000_000_000);
     expect(resolutionAccount.stakeAccount.toString()).to.equal(stakeKeypair.publicKey.toString());
+    expect(resolutionAccount.validatorVoteAccount.toString()).to.equal(voteAccountPubkey.toString());
+    expect(resolutionAccount.createdSlot.toNumber()).to.equal(Number((await banksClient.getClock()).slot));
 
 
   });
 
+  it("initialize concurrent resolution", async () => {
+    secondStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(1), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Second Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: secondStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([secondStakeKeypair]).rpc();
+
+    [secondResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(1).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const firstResolution = await program.account.resolutionAccount.fetch(resolutionPDA);
+    const secondResolution = await program.account.resolutionAccount.fetch(secondResolutionPDA);
+
+    expect(firstResolution.nonce.toNumber()).to.equal(0);
+    expect(firstResolution.text).to.equal("Hello World");
+    expect(secondResolution.nonce.toNumber()).to.equal(1);
+    expect(secondResolution.text).to.equal("Second Resolution");
+  })
+
+  it("update text before approval", async () => {
+    await program.methods.updateText("Second Resolution, clarified").accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: secondResolutionPDA,
+    }).signers([payer]).rpc();
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(secondResolutionPDA);
+    expect(resolutionAccount.text).to.equal("Second Resolution, clarified");
+  })
+
+  it("replace approver before approval", async () => {
+    try {
+      await program.methods.replaceApprover(approverC.publicKey, approverB.publicKey).accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: secondResolutionPDA,
+        registry: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidApprover");
+    }
+
+    await program.methods.replaceApprover(approverA.publicKey, approverB.publicKey).accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: secondResolutionPDA,
+      registry: null,
+    }).signers([payer]).rpc();
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(secondResolutionPDA);
+    expect(resolutionAccount.approvers.length).to.equal(1);
+    expect(resolutionAccount.approvers[0].toString()).to.equal(approverB.publicKey.toString());
+  })
+
   it("approve resolution", async () => {
-   await program.methods.approveResolution().accountsStrict({
+   await program.methods.approveResolution("verified gym check-ins").accountsStrict({
     signer: approverA.publicKey,
     owner: payer.publicKey,
     resolutionAccount: resolutionPDA,
@@ -127,6 +232,25 @@ describe("resolution", async () => {
     const resolutionAccount = await program.account.resolutionAccount.fetch(resolutionPDA);
     expect(resolutionAccount.approvedBy.length).to.equal(1);
     expect(resolutionAccount.approvedBy[0].toString()).to.equal(approverA.publicKey.toString());
+    expect(resolutionAccount.approvalNotes[0]).to.equal("verified gym check-ins");
+    const clock = await banksClient.getClock();
+    expect(resolutionAccount.approvedAt.length).to.equal(1);
+    expect(resolutionAccount.approvedAt[0].toString()).to.equal(clock.unixTimestamp.toString());
+  })
+
+  it("approval note too long", async () => {
+    try {
+      await program.methods.approveResolution("a".repeat(101)).accountsStrict({
+        signer: approverB.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: resolutionPDA,
+      }).signers([approverB]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("NoteTooLong");
+    }
   })
 
   it("double approval not allowed", async () => {
@@ -135,7 +259,7 @@ describe("resolution", async () => {
     await new Promise(resolve => setTimeout(resolve, 500));
 
     try {
-      await program.methods.approveResolution().accountsStrict({
+      await program.methods.approveResolution("").accountsStrict({
         signer: approverA.publicKey,
         owner: payer.publicKey,
         resolutionAccount: resolutionPDA,
@@ -148,16 +272,200 @@ describe("resolution", async () => {
     }
   })
 
+  it("revoke approval", async () => {
+    try {
+      await program.methods.revokeApproval().accountsStrict({
+        signer: approverB.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: resolutionPDA,
+      }).signers([approverB]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidApprover");
+    }
+
+    await program.methods.revokeApproval().accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: resolutionPDA,
+    }).signers([approverA]).rpc();
+
+    let resolutionAccount = await program.account.resolutionAccount.fetch(resolutionPDA);
+    expect(resolutionAccount.approvedBy.length).to.equal(0);
+
+    // Add small delay to ensure clock update is processed
+    await new Promise(resolve => setTimeout(resolve, 500));
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: resolutionPDA,
+    }).signers([approverA]).rpc();
+
+    resolutionAccount = await program.account.resolutionAccount.fetch(resolutionPDA);
+    expect(resolutionAccount.approvedBy.length).to.equal(1);
+  })
+
+  it("extend lockup", async () => {
+    try {
+      await program.methods.extendLockup(new anchor.BN(0)).accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: stakeKeypair.publicKey,
+        resolutionAccount: resolutionPDA,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        custodian: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidLockupDuration");
+    }
+
+    const resolutionBefore = await program.account.resolutionAccount.fetch(resolutionPDA);
+
+    await program.methods.extendLockup(new anchor.BN(24 * 60 * 60)).accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: stakeKeypair.publicKey,
+      resolutionAccount: resolutionPDA,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      custodian: null,
+    }).signers([payer]).rpc();
+
+    const resolutionAfter = await program.account.resolutionAccount.fetch(resolutionPDA);
+    expect(resolutionAfter.endTime.toNumber()).to.equal(resolutionBefore.endTime.toNumber() + 24 * 60 * 60);
+
+    // Lockup.unix_timestamp lives after the enum tag, rent reserve and authorities
+    const stakeAccountInfo = await banksClient.getAccount(stakeKeypair.publicKey);
+    const lockupTimestamp = Buffer.from(stakeAccountInfo.data).readBigInt64LE(76);
+    expect(lockupTimestamp.toString()).to.equal(resolutionAfter.endTime.toString());
+    expect(resolutionAfter.extensionCount).to.equal(1);
+  })
+
+  it("extension limit", async () => {
+    // distinct amounts keep the transactions from being deduplicated
+    const extendLockup = (days: number) =>
+      program.methods.extendLockup(new anchor.BN(days * 24 * 60 * 60)).accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: stakeKeypair.publicKey,
+        resolutionAccount: resolutionPDA,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        custodian: null,
+      }).signers([payer]).rpc();
+
+    await extendLockup(2);
+    await extendLockup(3);
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(resolutionPDA);
+    expect(resolutionAccount.extensionCount).to.equal(3);
+
+    try {
+      await extendLockup(4);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("TooManyExtensions");
+    }
+  })
+
+  it("add stake", async () => {
+    try {
+      await program.methods.addStake(new anchor.BN(0)).accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: stakeKeypair.publicKey,
+        resolutionAccount: resolutionPDA,
+        stats: statsPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidStakeAmount");
+    }
+
+    const stakeAccountBalanceBefore = await banksClient.getBalance(stakeKeypair.publicKey);
+
+    await program.methods.addStake(new anchor.BN(addedStake.toString())).accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: stakeKeypair.publicKey,
+      resolutionAccount: resolutionPDA,
+      stats: statsPDA,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    }).signers([payer]).rpc();
+
+    const stakeAccountBalanceAfter = await banksClient.getBalance(stakeKeypair.publicKey);
+    expect(stakeAccountBalanceAfter).equals(stakeAccountBalanceBefore + addedStake);
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(resolutionPDA);
+    expect(resolutionAccount.stakeAmount.toString()).to.equal((stakeAmount + addedStake).toString());
+  })
+
+  it("deactivate twice not allowed", async () => {
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: secondStakeKeypair.publicKey,
+      resolutionAccount: secondResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    try {
+      await program.methods.deactivateResolutionStake().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: secondStakeKeypair.publicKey,
+        resolutionAccount: secondResolutionPDA,
+        secondStakeAccount: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    } catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("AlreadyDeactivating");
+    }
+  })
+
+  it("update text after approval not allowed", async () => {
+    try {
+      await program.methods.updateText("Rewritten goal").accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: resolutionPDA,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("AlreadyApproved");
+    }
+  })
+
   it("attempt close before approval", async () => {
     try {
       await program.methods.closeResolution().accountsStrict({
         owner: payer.publicKey,
         stakeAccount: stakeKeypair.publicKey,
         resolutionAccount: resolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        secondStakeAccount: null,
+        charity: charity.publicKey,
+        recipient: null,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
         stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
         incineratorAccount: incineratorPubkey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        custodian: null,
+        details: null,
+        vault: null,
       }).signers([payer]).rpc();
       assert.fail("Expected an error to be thrown");
     } catch (error) {
@@ -167,13 +475,13 @@ describe("resolution", async () => {
   })
 
   it("close resolution after approvals", async () => {
-    await program.methods.approveResolution().accountsStrict({
+    await program.methods.approveResolution("").accountsStrict({
       signer: approverB.publicKey,
       owner: payer.publicKey,
       resolutionAccount: resolutionPDA,
      }).signers([approverB]).rpc();
 
-     await program.methods.approveResolution().accountsStrict({
+     await program.methods.approveResolution("").accountsStrict({
       signer: approverC.publicKey,
       owner: payer.publicKey,
       resolutionAccount: resolutionPDA,
@@ -185,6 +493,7 @@ describe("resolution", async () => {
       owner: payer.publicKey,
       stakeAccount: stakeKeypair.publicKey,
       resolutionAccount: resolutionPDA,
+      secondStakeAccount: null,
       clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
       stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
     }).signers([payer]).rpc();
@@ -197,10 +506,19 @@ describe("resolution", async () => {
       owner: payer.publicKey,
       stakeAccount: stakeKeypair.publicKey,
       resolutionAccount: resolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
       clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
       stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
       stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
       incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
     }).signers([payer]).rpc();
 
     const payerBalanceAfter = await banksClient.getBalance(payer.publicKey);
@@ -208,16 +526,16 @@ describe("resolution", async () => {
     
     expect(stakeAccountBalanceAfter).equals(0n);
   
-    if(payerBalanceAfter + txFees !== payerBalanceBefore + stakeAmount + resolutionAccountRent + stakeAccountRent) {
+    if(payerBalanceAfter + txFees !== payerBalanceBefore + stakeAmount + addedStake + resolutionAccountRent + stakeAccountRent) {
       assert.fail("Expected withdrawal to be greater than stake amount");
     }
   })
 
   it("close resolution after lockup", async () => {
-    const [newResolutionPDA,] = await PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer()], program.programId);
+    const [newResolutionPDA,] = await PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)], program.programId);
     const newStakeKeypair = Keypair.generate();
 
-    await program.methods.initializeResolution(new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60*1000), "New Resolution").accounts({
+    await program.methods.initializeResolution(new anchor.BN(0), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 3, charity.publicKey, 1000, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
       owner: payer.publicKey,
       stakeAccount: newStakeKeypair.publicKey,
       validatorVoteAccount: voteAccountPubkey,
@@ -247,6 +565,7 @@ describe("resolution", async () => {
       owner: payer.publicKey,
       stakeAccount: newStakeKeypair.publicKey,
       resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
       clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
       stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
     }).signers([payer]).rpc();
@@ -256,10 +575,19 @@ describe("resolution", async () => {
         owner: payer.publicKey,
         stakeAccount: newStakeKeypair.publicKey,
         resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        secondStakeAccount: null,
+        charity: charity.publicKey,
+        recipient: null,
         clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
         stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
         stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
         incineratorAccount: incineratorPubkey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        custodian: null,
+        details: null,
+        vault: null,
       }).signers([payer]).rpc();
       assert.fail("Expected an error to be thrown");
     } catch (error) {
@@ -274,6 +602,7 @@ describe("resolution", async () => {
     await new Promise(resolve => setTimeout(resolve, 100));
 
     const payerBalanceBefore = await banksClient.getBalance(payer.publicKey);
+    const charityBalanceBefore = await banksClient.getBalance(charity.publicKey);
     const stakeAccountBalanceBefore = await banksClient.getBalance(newStakeKeypair.publicKey);
     console.log("Before", {
       payerBalanceBefore: payerBalanceBefore.toString(),
@@ -284,18 +613,34 @@ describe("resolution", async () => {
       owner: payer.publicKey,
       stakeAccount: newStakeKeypair.publicKey,
       resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
       clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
       stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
       stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
       incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
     }).signers([payer]).rpc();
 
     const payerBalanceAfter = await banksClient.getBalance(payer.publicKey);
     const stakeAccountBalanceAfter = await banksClient.getBalance(newStakeKeypair.publicKey);
 
+    // fully drained, rent reserve included, so the stake account is gone
     expect(stakeAccountBalanceAfter).equals(0n);
+    expect(await banksClient.getAccount(newStakeKeypair.publicKey)).to.be.null;
 
-    if(payerBalanceAfter + txFees !== payerBalanceBefore + stakeAmount + resolutionAccountRent) {
+    // 10% of the returned stake goes to the charity
+    const penaltyAmount = stakeAmount / 10n;
+    const charityBalanceAfter = await banksClient.getBalance(charity.publicKey);
+    expect(charityBalanceAfter).equals(charityBalanceBefore + penaltyAmount);
+
+    if(payerBalanceAfter + txFees !== payerBalanceBefore + stakeAmount - penaltyAmount + resolutionAccountRent) {
       assert.fail("Expected withdrawal to be greater than stake amount");
     }
 
@@ -305,7 +650,7 @@ describe("resolution", async () => {
     try {
       const newStakeKeypair = Keypair.generate();
 
-    await program.methods.initializeResolution(new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60*1000), "New Resolution").accounts({
+    await program.methods.initializeResolution(new anchor.BN(0), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
       owner: payer.publicKey,
       stakeAccount: newStakeKeypair.publicKey,
       validatorVoteAccount: voteAccountPubkey,
@@ -340,7 +685,7 @@ describe("resolution", async () => {
     try {
       const newStakeKeypair = Keypair.generate();
 
-    await program.methods.initializeResolution(new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60*1000), "New Resolution").accounts({
+    await program.methods.initializeResolution(new anchor.BN(0), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
       owner: payer.publicKey,
       stakeAccount: newStakeKeypair.publicKey,
       validatorVoteAccount: voteAccountPubkey,
@@ -375,7 +720,49 @@ describe("resolution", async () => {
     try {
       const newStakeKeypair = Keypair.generate();
 
-    await program.methods.initializeResolution(new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60*1000), "New Resolution").accounts({
+    await program.methods.initializeResolution(new anchor.BN(0), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([]).signers([payer, newStakeKeypair]).rpc();
+    assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidNumApprovers");
+    }
+
+    try {
+      const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(0), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts(
+      Array.from({ length: 6 }, () => ({
+        isSigner: false,
+        isWritable: false,
+        pubkey: Keypair.generate().publicKey,
+      }))
+    ).signers([payer, newStakeKeypair]).rpc();
+    assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidNumApprovers");
+    }
+  })
+
+  it("invalid threshold", async () => {
+    try {
+      const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(0), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 0, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
       owner: payer.publicKey,
       stakeAccount: newStakeKeypair.publicKey,
       validatorVoteAccount: voteAccountPubkey,
@@ -391,14 +778,3815 @@ describe("resolution", async () => {
         isSigner: false,
         isWritable: false,
         pubkey: approverB.publicKey,
-      }
+      },
     ]).signers([payer, newStakeKeypair]).rpc();
     assert.fail("Expected an error to be thrown");
     }
     catch (error) {
       expect(error).to.be.instanceOf(AnchorError);
-      expect(error.error.errorCode.code).to.equal("InvalidNumApprovers");
+      expect(error.error.errorCode.code).to.equal("InvalidThreshold");
+    }
+  })
+
+  it("time remaining is zero after the deadline", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(2), new anchor.BN(5_000_000_000), new anchor.BN(24 * 60 * 60), "Goal", "New Resolution", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverB.publicKey,
+      },
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverC.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(2).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const timeRemaining = await program.methods.getTimeRemaining().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).view();
+    expect(timeRemaining.toNumber()).to.equal(24 * 60 * 60);
+
+    const currentClock = await banksClient.getClock();
+    await setClockTimestamp(context, Number(currentClock.unixTimestamp) + 2 * 24 * 60 * 60);
+
+    const timeRemainingAfter = await program.methods.getTimeRemaining().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).view();
+    expect(timeRemainingAfter.toNumber()).to.equal(0);
+  })
+
+  it("approval rejected after the deadline", async () => {
+    const [expiredResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(2).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    try {
+      await program.methods.approveResolution("").accountsStrict({
+        signer: approverA.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: expiredResolutionPDA,
+      }).signers([approverA]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ResolutionExpired");
     }
   })
 
+  it("stake amount below minimum delegation", async () => {
+    try {
+      const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(3), new anchor.BN(1), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+    assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("StakeAmountTooLow");
+    }
+  })
+
+  it("cancel resolution in activation epoch", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(4), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(4).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const payerBalanceBefore = await banksClient.getBalance(payer.publicKey);
+
+    await program.methods.cancelResolution().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      custodian: null,
+      details: null,
+      vault: null,
+    }).signers([payer]).rpc();
+
+    const payerBalanceAfter = await banksClient.getBalance(payer.publicKey);
+    expect(await banksClient.getBalance(newStakeKeypair.publicKey)).equals(0n);
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+
+    if(payerBalanceAfter + txFees !== payerBalanceBefore + stakeAmount + resolutionAccountRent + stakeAccountRent) {
+      assert.fail("Expected the full stake and rent to be returned");
+    }
+  })
+
+  it("invalid charity", async () => {
+    try {
+      const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(3), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, approverA.publicKey, 1000, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+    assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidCharity");
+    }
+  })
+
+  it("close is unaffected by edits to the approver list", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(5), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(5).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.approveResolution("done").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    // Grow the approver list behind the program's back
+    const accountInfo = await banksClient.getAccount(newResolutionPDA);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    resolutionAccount.approvers = [approverA.publicKey, approverB.publicKey, approverC.publicKey];
+    const encoded = await program.coder.accounts.encode("ResolutionAccount", resolutionAccount);
+    const data = Buffer.alloc(accountInfo.data.length);
+    encoded.copy(data);
+    context.setAccount(newResolutionPDA, { ...accountInfo, data });
+
+    const tampered = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(tampered.approvers.length).to.equal(3);
+    expect(tampered.requiredApprovals).to.equal(1);
+
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    await program.methods.closeResolution().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    }).signers([payer]).rpc();
+
+    expect(await banksClient.getBalance(newStakeKeypair.publicKey)).equals(0n);
+  })
+
+  it("invalid title", async () => {
+    try {
+      const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(3), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "   ", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+    assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidTitle");
+    }
+  })
+
+  it("text validated by byte length", async () => {
+    const initializeWithText = async (nonce: number, text: string) => {
+      const newStakeKeypair = Keypair.generate();
+
+      await program.methods.initializeResolution(new anchor.BN(nonce), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", text, 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+    };
+
+    // each emoji is four bytes, so 65 of them are only 130 UTF-16 units but 260 bytes
+    for (const text of [" \t\n ", "🏃".repeat(65)]) {
+      try {
+        await initializeWithText(23, text);
+        assert.fail("Expected an error to be thrown");
+      }
+      catch (error) {
+        expect(error).to.be.instanceOf(AnchorError);
+        expect(error.error.errorCode.code).to.equal("InvalidText");
+      }
+    }
+
+    await initializeWithText(23, "🏃".repeat(64));
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(23).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.text).to.equal("🏃".repeat(64));
+  })
+
+  it("lockup duration bounds", async () => {
+    const minLockupDuration = 24 * 60 * 60;
+    const maxLockupDuration = 2 * 365 * 24 * 60 * 60;
+
+    // i64::MAX would overflow lockup_end if it ever got past the bounds check
+    for (const lockupDuration of [0, -1, minLockupDuration - 1, maxLockupDuration + 1, "9223372036854775807"]) {
+      try {
+        const newStakeKeypair = Keypair.generate();
+
+      await program.methods.initializeResolution(new anchor.BN(6), new anchor.BN(5_000_000_000), new anchor.BN(lockupDuration), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+      assert.fail("Expected an error to be thrown");
+      }
+      catch (error) {
+        expect(error).to.be.instanceOf(AnchorError);
+        expect(error.error.errorCode.code).to.equal("InvalidLockupDuration");
+      }
+    }
+
+    maxLockupStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(6), new anchor.BN(5_000_000_000), new anchor.BN(maxLockupDuration), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: maxLockupStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, maxLockupStakeKeypair]).rpc();
+
+    [maxLockupResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(6).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(maxLockupResolutionPDA);
+    expect(resolutionAccount.endTime.toNumber() - resolutionAccount.startTime.toNumber()).to.equal(maxLockupDuration);
+  })
+
+  it("preview withdrawable", async () => {
+    const simulation = await program.methods.previewWithdrawable().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: maxLockupStakeKeypair.publicKey,
+      resolutionAccount: maxLockupResolutionPDA,
+    }).simulate();
+
+    const logValue = (prefix: string) => {
+      const log = simulation.raw.find((line) => line.startsWith(`Program log: ${prefix}: `));
+      return BigInt(log.split(": ").pop());
+    };
+
+    expect(logValue("Total lamports")).equals(stakeAmount + stakeAccountRent);
+    expect(logValue("Rent exempt reserve")).equals(stakeAccountRent);
+  })
+
+  it("initialize resolution with typed approvers", async () => {
+    typedStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolutionV2(new anchor.BN(7), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Typed Approvers", 2, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      base: {
+        owner: payer.publicKey,
+        stakeAccount: typedStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      },
+      approverA: approverA.publicKey,
+      approverB: approverB.publicKey,
+      approverC: approverC.publicKey,
+    }).signers([typedStakeKeypair]).rpc();
+
+    [typedResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(7).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(typedResolutionPDA);
+
+    expect(resolutionAccount.owner.toString()).to.equal(payer.publicKey.toString());
+    expect(resolutionAccount.approvers.map((approver) => approver.toString())).to.deep.equal([
+      approverA.publicKey.toString(),
+      approverB.publicKey.toString(),
+      approverC.publicKey.toString(),
+    ]);
+    expect(resolutionAccount.requiredApprovals).to.equal(2);
+    expect(resolutionAccount.stakeAccount.toString()).to.equal(typedStakeKeypair.publicKey.toString());
+  })
+
+  it("approve extension", async () => {
+    const extensionSeconds = 24 * 60 * 60;
+    const resolutionBefore = await program.account.resolutionAccount.fetch(typedResolutionPDA);
+
+    const approveExtension = (approver: Keypair) =>
+      program.methods.approveExtension(new anchor.BN(extensionSeconds)).accountsStrict({
+        signer: approver.publicKey,
+        owner: payer.publicKey,
+        stakeAccount: typedStakeKeypair.publicKey,
+        resolutionAccount: typedResolutionPDA,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        custodian: null,
+      }).signers([approver]).rpc();
+
+    await approveExtension(approverA);
+
+    const pending = await program.account.resolutionAccount.fetch(typedResolutionPDA);
+    expect(pending.pendingExtension.toNumber()).to.equal(extensionSeconds);
+    expect(pending.extensionApprovedBy.length).to.equal(1);
+    expect(pending.endTime.toNumber()).to.equal(resolutionBefore.endTime.toNumber());
+
+    try {
+      await approveExtension(approverA);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("AlreadyApproved");
+    }
+
+    await approveExtension(approverB);
+
+    const resolutionAfter = await program.account.resolutionAccount.fetch(typedResolutionPDA);
+    expect(resolutionAfter.endTime.toNumber()).to.equal(resolutionBefore.endTime.toNumber() + extensionSeconds);
+    expect(resolutionAfter.pendingExtension.toNumber()).to.equal(0);
+    expect(resolutionAfter.extensionApprovedBy.length).to.equal(0);
+
+    const stakeAccountInfo = await banksClient.getAccount(typedStakeKeypair.publicKey);
+    const lockupTimestamp = Buffer.from(stakeAccountInfo.data).readBigInt64LE(76);
+    expect(lockupTimestamp.toString()).to.equal(resolutionAfter.endTime.toString());
+  })
+
+  it("status covers each branch", async () => {
+    const getStatus = (resolutionAccount: PublicKey) =>
+      program.methods.getStatus().accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount,
+      }).view();
+
+    const [expiredResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(2).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    expect(await getStatus(typedResolutionPDA)).to.deep.equal({ activeLocked: {} });
+    expect(await getStatus(expiredResolutionPDA)).to.deep.equal({ expiredUnapproved: {} });
+
+    for (const approver of [approverA, approverB]) {
+      await program.methods.approveResolution("Done").accountsStrict({
+        signer: approver.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: typedResolutionPDA,
+      }).signers([approver]).rpc();
+    }
+
+    expect(await getStatus(typedResolutionPDA)).to.deep.equal({ activeApproved: {} });
+
+    // closed resolutions have no account left to read
+    try {
+      await getStatus(resolutionPDA);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error.message).to.not.equal("Expected an error to be thrown");
+    }
+  })
+
+  it("close before deactivating not allowed", async () => {
+    const [expiredResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(2).toArrayLike(Buffer, "le", 8)], program.programId);
+    const expiredResolution = await program.account.resolutionAccount.fetch(expiredResolutionPDA);
+
+    try {
+      await program.methods.closeResolution().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: expiredResolution.stakeAccount,
+        resolutionAccount: expiredResolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        secondStakeAccount: null,
+        charity: charity.publicKey,
+        recipient: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        incineratorAccount: incineratorPubkey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        custodian: null,
+        details: null,
+        vault: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    } catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("StakeNotDeactivated");
+    }
+  })
+
+  it("sweep dust from a closed resolution", async () => {
+    const [closedResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(5).toArrayLike(Buffer, "le", 8)], program.programId);
+    const rent = await banksClient.getRent();
+    const dust = rent.minimumBalance(0n);
+
+    const tx = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({
+        fromPubkey: payer.publicKey,
+        toPubkey: closedResolutionPDA,
+        lamports: dust,
+      }),
+    );
+    await provider.sendAndConfirm(tx, [payer]);
+
+    const payerBalanceBefore = await banksClient.getBalance(payer.publicKey);
+
+    await program.methods.sweepDust(new anchor.BN(5)).accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: closedResolutionPDA,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    }).signers([payer]).rpc();
+
+    const payerBalanceAfter = await banksClient.getBalance(payer.publicKey);
+    expect(payerBalanceAfter + txFees).equals(payerBalanceBefore + dust);
+    expect(await banksClient.getBalance(closedResolutionPDA)).equals(0n);
+
+    // still open, so the PDA belongs to the program and its stake hasn't been withdrawn
+    try {
+      await program.methods.sweepDust(new anchor.BN(6)).accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: maxLockupResolutionPDA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("AccountNotSystemOwned");
+    }
+  })
+
+  it("split stake and close both stake accounts", async () => {
+    const splitStakeKeypair = Keypair.generate();
+    const splitAmount = 2_000_000_000n;
+
+    const splitStake = (splitStakeAccount: Keypair) =>
+      program.methods.splitStake(new anchor.BN(splitAmount.toString())).accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: typedStakeKeypair.publicKey,
+        splitStakeAccount: splitStakeAccount.publicKey,
+        resolutionAccount: typedResolutionPDA,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([payer, splitStakeAccount]).rpc();
+
+    await splitStake(splitStakeKeypair);
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(typedResolutionPDA);
+    expect(resolutionAccount.secondStakeAccount.toString()).to.equal(splitStakeKeypair.publicKey.toString());
+    expect(await banksClient.getBalance(splitStakeKeypair.publicKey)).equals(splitAmount + stakeAccountRent);
+
+    try {
+      await splitStake(Keypair.generate());
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("StakeAlreadySplit");
+    }
+
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: typedStakeKeypair.publicKey,
+      resolutionAccount: typedResolutionPDA,
+      secondStakeAccount: splitStakeKeypair.publicKey,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    const payerBalanceBefore = await banksClient.getBalance(payer.publicKey);
+    const stakeBalances = await banksClient.getBalance(typedStakeKeypair.publicKey) + await banksClient.getBalance(splitStakeKeypair.publicKey);
+
+    await program.methods.closeResolution().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: typedStakeKeypair.publicKey,
+      resolutionAccount: typedResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: splitStakeKeypair.publicKey,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    }).signers([payer]).rpc();
+
+    const payerBalanceAfter = await banksClient.getBalance(payer.publicKey);
+    expect(await banksClient.getBalance(typedStakeKeypair.publicKey)).equals(0n);
+    expect(await banksClient.getBalance(splitStakeKeypair.publicKey)).equals(0n);
+    expect(payerBalanceAfter + txFees).equals(payerBalanceBefore + stakeBalances + resolutionAccountRent);
+  })
+
+  it("pre-funded stake account rejected", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const rent = await banksClient.getRent();
+
+    const tx = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({
+        fromPubkey: payer.publicKey,
+        toPubkey: newStakeKeypair.publicKey,
+        lamports: rent.minimumBalance(0n),
+      }),
+    );
+    await provider.sendAndConfirm(tx, [payer]);
+
+    try {
+      await program.methods.initializeResolution(new anchor.BN(3), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("StakeAccountAlreadyInitialized");
+    }
+  })
+
+
+  it("withdraw destination must not be the zero pubkey", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    try {
+      await program.methods.initializeResolution(new anchor.BN(8), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, PublicKey.default, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidWithdrawDestination");
+    }
+  })
+
+  it("close pays out to a separate withdraw destination", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const coldWallet = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(8), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, coldWallet.publicKey, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(8).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.withdrawDestination.toString()).to.equal(coldWallet.publicKey.toString());
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    const stakeBalance = await banksClient.getBalance(newStakeKeypair.publicKey);
+
+    await program.methods.closeResolution().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: coldWallet.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    }).signers([payer, coldWallet]).rpc();
+
+    expect(await banksClient.getBalance(newStakeKeypair.publicKey)).equals(0n);
+    expect(await banksClient.getBalance(coldWallet.publicKey)).equals(stakeBalance);
+  })
+
+
+  it("deactivate and close in one transaction", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(9), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(9).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const closeAccounts = {
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    };
+
+    try {
+      await program.methods.deactivateAndClose().accountsStrict(closeAccounts).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("DeactivationPending");
+    }
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    // still in the activation epoch, so deactivating leaves no effective stake
+    await program.methods.deactivateAndClose().accountsStrict(closeAccounts).signers([payer]).rpc();
+
+    expect(await banksClient.getBalance(newStakeKeypair.publicKey)).equals(0n);
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+  })
+
+
+  it("stake amount above the maximum rejected", async () => {
+    const maxStakeAmount = new anchor.BN("1000000000000000000");
+
+    for (const [amount, rejected] of [[maxStakeAmount.addn(1), true], [maxStakeAmount, false]] as const) {
+      const newStakeKeypair = Keypair.generate();
+
+      try {
+        await program.methods.initializeResolution(new anchor.BN(10), amount, new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+          owner: payer.publicKey,
+          stakeAccount: newStakeKeypair.publicKey,
+          validatorVoteAccount: voteAccountPubkey,
+          stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+          stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+        }).remainingAccounts([
+          {
+            isSigner: false,
+            isWritable: false,
+            pubkey: approverA.publicKey,
+          },
+        ]).signers([payer, newStakeKeypair]).rpc();
+        assert.fail("Expected an error to be thrown");
+      }
+      catch (error) {
+        // at the maximum itself the cap passes and funding the stake account fails instead
+        if (rejected) {
+          expect(error).to.be.instanceOf(AnchorError);
+          expect(error.error.errorCode.code).to.equal("StakeAmountTooHigh");
+        } else {
+          expect(error.error?.errorCode?.code).to.not.equal("StakeAmountTooHigh");
+        }
+      }
+    }
+  })
+
+
+  it("pending approvers shrink as approvals come in", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(11), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverB.publicKey,
+      },
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverC.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(11).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const getPendingApprovers = async () => {
+      const pending: PublicKey[] = await program.methods.getPendingApprovers().accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).view();
+      return pending.map((approver) => approver.toString());
+    };
+
+    expect(await getPendingApprovers()).to.deep.equal([
+      approverA.publicKey.toString(),
+      approverB.publicKey.toString(),
+      approverC.publicKey.toString(),
+    ]);
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverB.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverB]).rpc();
+
+    expect(await getPendingApprovers()).to.deep.equal([
+      approverA.publicKey.toString(),
+      approverC.publicKey.toString(),
+    ]);
+
+    for (const approver of [approverA, approverC]) {
+      await program.methods.approveResolution("").accountsStrict({
+        signer: approver.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approver]).rpc();
+    }
+
+    expect(await getPendingApprovers()).to.deep.equal([]);
+  })
+
+
+  it("emergency release needs every approver", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(12), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 1000, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverB.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(12).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const approveEmergencyRelease = (approver: Keypair) =>
+      program.methods.approveEmergencyRelease().accountsStrict({
+        signer: approver.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approver]).rpc();
+
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    const closeResolution = () =>
+      program.methods.closeResolution().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        secondStakeAccount: null,
+        charity: charity.publicKey,
+        recipient: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        incineratorAccount: incineratorPubkey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        custodian: null,
+        details: null,
+        vault: null,
+      }).signers([payer]).rpc();
+
+    await approveEmergencyRelease(approverA);
+
+    // one of two isn't enough even though the goal threshold is one
+    try {
+      await closeResolution();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("LockupInForce");
+    }
+
+    await approveEmergencyRelease(approverB);
+
+    const charityBalanceBefore = await banksClient.getBalance(charity.publicKey);
+
+    await closeResolution();
+
+    expect(await banksClient.getBalance(newStakeKeypair.publicKey)).equals(0n);
+    // the approved path takes no penalty
+    expect(await banksClient.getBalance(charity.publicKey)).equals(charityBalanceBefore);
+  })
+
+
+  it("pause blocks new resolutions only", async () => {
+    const [configPDA,] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const [openResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(13).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const setPaused = (paused: boolean, authority: Keypair) =>
+      program.methods.setPaused(paused).accountsStrict({
+        authority: authority.publicKey,
+        config: configPDA,
+      }).signers([authority]).rpc();
+
+    const initialize = (nonce: number) => {
+      const stakeKeypair = Keypair.generate();
+      return program.methods.initializeResolution(new anchor.BN(nonce), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: stakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, stakeKeypair]).rpc();
+    };
+
+    await initialize(13);
+
+    try {
+      await setPaused(true, approverA);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ConstraintHasOne");
+    }
+
+    await setPaused(true, payer);
+
+    try {
+      await initialize(14);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ProgramPaused");
+    }
+
+    // existing resolutions keep working while paused
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: openResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    await setPaused(false, payer);
+
+    await initialize(14);
+  })
+
+
+  it("delinquent validator rejected", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    try {
+      await program.methods.initializeResolution(new anchor.BN(15), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: delinquentVoteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("DelinquentValidator");
+    }
+  })
+
+
+  it("close pays out to a separate recipient", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const recipient = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(16), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(16).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    const stakeBalance = await banksClient.getBalance(newStakeKeypair.publicKey);
+    const payerBalanceBefore = await banksClient.getBalance(payer.publicKey);
+
+    await program.methods.closeResolution().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: recipient.publicKey,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    }).signers([payer]).rpc();
+
+    // the stake goes to the recipient, the resolution rent still goes back to the owner
+    expect(await banksClient.getBalance(recipient.publicKey)).equals(stakeBalance);
+    expect(await banksClient.getBalance(payer.publicKey) + txFees).equals(payerBalanceBefore + resolutionAccountRent);
+  })
+
+
+  it("batch approve skips resolutions the signer already approved", async () => {
+    const resolutionPDAs: PublicKey[] = [];
+    for (const nonce of [65, 66]) {
+      const newStakeKeypair = Keypair.generate();
+
+      await program.methods.initializeResolution(new anchor.BN(nonce), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Batch Approve", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+      resolutionPDAs.push(PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(nonce).toArrayLike(Buffer, "le", 8)], program.programId)[0]);
+    }
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: resolutionPDAs[0],
+    }).signers([approverA]).rpc();
+
+    const batch = (owner: PublicKey) => program.methods.batchApprove("batched").accountsStrict({
+      signer: approverA.publicKey,
+      owner,
+    }).remainingAccounts(resolutionPDAs.map((pubkey) => ({
+      isSigner: false,
+      isWritable: true,
+      pubkey,
+    }))).signers([approverA]);
+
+    // the resolutions belong to payer, not to whoever is passed as owner
+    try {
+      await batch(approverB.publicKey).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidResolutionAccount");
+    }
+
+    const simulation = await batch(payer.publicKey).simulate();
+    expect(simulation.raw).to.include(`Program log: Skipped: ${resolutionPDAs[0].toString()}`);
+    expect(simulation.raw).to.include("Program log: Approved: 1");
+
+    await batch(payer.publicKey).rpc();
+
+    const [first, second] = await Promise.all(resolutionPDAs.map((pubkey) => program.account.resolutionAccount.fetch(pubkey)));
+    expect(first.approvalNotes).to.deep.equal([""]);
+    expect(second.approvedBy.map((approver) => approver.toString())).to.deep.equal([approverA.publicKey.toString()]);
+    expect(second.approvalNotes).to.deep.equal(["batched"]);
+  })
+
+
+  it("category is stored and validated", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const initialize = (category: number) =>
+      program.methods.initializeResolution(new anchor.BN(17), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, category, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+
+    try {
+      await initialize(7);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidCategory");
+    }
+
+    await initialize(1);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(17).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.category).to.equal(1);
+  })
+
+
+  it("reactivate stake after deactivating", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(18), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(18).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    // Delegation.deactivation_epoch follows the meta, voter, stake and activation epoch
+    const deactivationEpoch = async () => {
+      const stakeAccountInfo = await banksClient.getAccount(newStakeKeypair.publicKey);
+      return Buffer.from(stakeAccountInfo.data).readBigUInt64LE(172);
+    };
+
+    const reactivateStake = (resolutionAccount: PublicKey, stakeAccount: PublicKey) =>
+      program.methods.reactivateStake().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount,
+        resolutionAccount,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      }).signers([payer]).rpc();
+
+    // a resolution that was never deactivated has nothing to rescind
+    const [activeResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(13).toArrayLike(Buffer, "le", 8)], program.programId);
+    const activeResolution = await program.account.resolutionAccount.fetch(activeResolutionPDA);
+
+    try {
+      await reactivateStake(activeResolutionPDA, activeResolution.stakeAccount);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("StakeNotDeactivating");
+    }
+
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    expect(await deactivationEpoch()).equals((await banksClient.getClock()).epoch);
+
+    await reactivateStake(newResolutionPDA, newStakeKeypair.publicKey);
+
+    expect(await deactivationEpoch()).equals(18446744073709551615n);
+  })
+
+
+  it("stats track locked stake", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const statsBefore = await program.account.stats.fetch(statsPDA);
+
+    await program.methods.initializeResolution(new anchor.BN(19), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 1000, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const statsOpen = await program.account.stats.fetch(statsPDA);
+    expect(statsOpen.totalLocked.toString()).to.equal(statsBefore.totalLocked.addn(5_000_000_000).toString());
+    expect(statsOpen.activeCount.toNumber()).to.equal(statsBefore.activeCount.toNumber() + 1);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(19).toArrayLike(Buffer, "le", 8)], program.programId);
+    const newResolution = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(newResolution.id.toString()).to.equal(statsBefore.nextId.toString());
+    expect(statsOpen.nextId.toString()).to.equal(statsBefore.nextId.addn(1).toString());
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    await program.methods.closeResolution().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    }).signers([payer]).rpc();
+
+    const statsAfter = await program.account.stats.fetch(statsPDA);
+    expect(statsAfter.totalLocked.toString()).to.equal(statsBefore.totalLocked.toString());
+    expect(statsAfter.activeCount.toNumber()).to.equal(statsBefore.activeCount.toNumber());
+  })
+
+
+  it("idempotent approval no-ops on retry", async () => {
+    const [openResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(18).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const approveIdempotent = (note: string) =>
+      program.methods.approveResolutionIdempotent(note).accountsStrict({
+        signer: approverA.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: openResolutionPDA,
+      }).signers([approverA]).rpc();
+
+    await approveIdempotent("first");
+    await approveIdempotent("retry");
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(openResolutionPDA);
+    expect(resolutionAccount.approvedBy.length).to.equal(1);
+    expect(resolutionAccount.approvalNotes[0]).to.equal("first");
+
+    try {
+      await program.methods.approveResolution("strict").accountsStrict({
+        signer: approverA.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: openResolutionPDA,
+      }).signers([approverA]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("AlreadyApproved");
+    }
+  })
+
+
+  it("initialize with an absolute lockup end", async () => {
+    const now = Number((await banksClient.getClock()).unixTimestamp);
+
+    const initializeAt = (endTimestamp: number) => {
+      const newStakeKeypair = Keypair.generate();
+      return program.methods.initializeResolutionAt(new anchor.BN(20), new anchor.BN(5_000_000_000), new anchor.BN(endTimestamp), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+    };
+
+    for (const endTimestamp of [now, now + 2 * 365 * 24 * 60 * 60 + 1]) {
+      try {
+        await initializeAt(endTimestamp);
+        assert.fail("Expected an error to be thrown");
+      }
+      catch (error) {
+        expect(error).to.be.instanceOf(AnchorError);
+        expect(error.error.errorCode.code).to.equal("InvalidLockupDuration");
+      }
+    }
+
+    const endTimestamp = now + 30 * 24 * 60 * 60;
+    await initializeAt(endTimestamp);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(20).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.endTime.toNumber()).to.equal(endTimestamp);
+  })
+
+
+  it("validator commission above the cap rejected", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    try {
+      // the test validator charges 100%
+      await program.methods.initializeResolution(new anchor.BN(21), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Resolution", 1, charity.publicKey, 0, 0, null, 1000, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("CommissionTooHigh");
+    }
+  })
+
+
+
+  it("stakeless resolution skips staking and closes on its own", async () => {
+    await program.methods.initializeResolution(new anchor.BN(22), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "No Stake", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(22).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.stakeAccount.toBase58()).to.equal(PublicKey.default.toBase58());
+    expect(resolutionAccount.stakeAmount.toNumber()).to.equal(0);
+
+    await program.methods.approveResolution("kept the streak").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    await program.methods.closeStakelessResolution().accounts({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+    }).signers([payer]).rpc();
+
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+  })
+
+
+
+  it("approver removed once every other approver consents", async () => {
+    await program.methods.initializeResolution(new anchor.BN(24), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Lost Approver", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB, approverC].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(24).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.approveResolution("done").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    const removeApprover = async (signer: Keypair, target: PublicKey) => {
+      await program.methods.removeApprover(target).accountsStrict({
+        signer: signer.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([signer]).rpc();
+    };
+
+    try {
+      await removeApprover(approverC, approverC.publicKey);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidApprover");
+    }
+
+    await removeApprover(approverA, approverC.publicKey);
+
+    let resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvers.length).to.equal(3);
+    expect(resolutionAccount.pendingRemoval.toString()).to.equal(approverC.publicKey.toString());
+
+    await removeApprover(approverB, approverC.publicKey);
+
+    resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvers.map((approver) => approver.toString())).to.deep.equal([approverA.publicKey.toString(), approverB.publicKey.toString()]);
+    expect(resolutionAccount.requiredApprovals).to.equal(2);
+    expect(resolutionAccount.approvedBy.length).to.equal(1);
+    expect(resolutionAccount.pendingRemoval).to.be.null;
+    expect(resolutionAccount.removalConsentedBy.length).to.equal(0);
+  })
+
+
+
+  it("can close explains why close would fail", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(25), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Close Preview", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(25).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const canClose = () =>
+      program.methods.canClose().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        secondStakeAccount: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      }).view();
+
+    expect(await canClose()).to.deep.equal({ lockupInForce: {} });
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    expect(await canClose()).to.deep.equal({ stakeNotDeactivated: {} });
+
+    // deactivated in its activation epoch, so nothing is left effective
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    expect(await canClose()).to.deep.equal({ ok: {} });
+  })
+
+
+
+  it("approvals accepted during the grace period", async () => {
+    const initializeWithGrace = async (gracePeriod: number) => {
+      await program.methods.initializeResolution(new anchor.BN(26), new anchor.BN(0), new anchor.BN(24 * 60 * 60), "Goal", "Grace Period", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(gracePeriod), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: null,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer]).rpc();
+    };
+
+    try {
+      await initializeWithGrace(31 * 24 * 60 * 60);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidGracePeriod");
+    }
+
+    await initializeWithGrace(2 * 24 * 60 * 60);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(26).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+
+    // past end_time but still inside the grace period
+    await setClockTimestamp(context, resolutionAccount.endTime.toNumber() + 24 * 60 * 60);
+
+    const canClose = () =>
+      program.methods.canClose().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: PublicKey.default,
+        resolutionAccount: newResolutionPDA,
+        secondStakeAccount: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      }).view();
+
+    expect(await canClose()).to.deep.equal({ notEnoughApprovals: {} });
+
+    try {
+      await program.methods.closeStakelessResolution().accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        details: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("NotEnoughApprovals");
+    }
+
+    await program.methods.approveResolution("made it in time").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    expect(await canClose()).to.deep.equal({ ok: {} });
+  })
+
+
+
+  it("named custodian has to sign lockup changes", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const custodian = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(27), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Named Custodian", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), custodian.publicKey, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(27).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    // Lockup.custodian follows the lockup timestamp and epoch
+    const stakeAccountInfo = await banksClient.getAccount(newStakeKeypair.publicKey);
+    const lockupCustodian = new PublicKey(Buffer.from(stakeAccountInfo.data).subarray(92, 124));
+    expect(lockupCustodian.toString()).to.equal(custodian.publicKey.toString());
+
+    try {
+      await program.methods.extendLockup(new anchor.BN(24 * 60 * 60)).accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        custodian: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidCustodian");
+    }
+
+    await program.methods.extendLockup(new anchor.BN(24 * 60 * 60)).accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      custodian: custodian.publicKey,
+    }).signers([payer, custodian]).rpc();
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.custodian.toString()).to.equal(custodian.publicKey.toString());
+    expect(resolutionAccount.extensionCount).to.equal(1);
+  })
+
+
+
+  it("update charity only before approval", async () => {
+    await program.methods.initializeResolution(new anchor.BN(28), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "New Charity", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(28).toArrayLike(Buffer, "le", 8)], program.programId);
+    const newCharity = Keypair.generate();
+
+    const updateCharity = (charityKey: PublicKey, penaltyBps: number) =>
+      program.methods.updateCharity(charityKey, penaltyBps).accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([payer]).rpc();
+
+    try {
+      await updateCharity(approverA.publicKey, 500);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidCharity");
+    }
+
+    await updateCharity(newCharity.publicKey, 500);
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.charity.toString()).to.equal(newCharity.publicKey.toString());
+    expect(resolutionAccount.penaltyBps).to.equal(500);
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    try {
+      await updateCharity(charity.publicKey, 1000);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("AlreadyApproved");
+    }
+  })
+
+
+
+  it("unrelated stake account rejected on deactivate", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(29), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Own Stake Only", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(29).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    // a stake account the payer controls directly, the resolution PDA isn't its staker
+    const unrelatedStakeKeypair = Keypair.generate();
+    const tx = new anchor.web3.Transaction().add(
+      anchor.web3.StakeProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        stakePubkey: unrelatedStakeKeypair.publicKey,
+        authorized: new anchor.web3.Authorized(payer.publicKey, payer.publicKey),
+        lamports: 5_000_000_000,
+      }),
+    );
+    await provider.sendAndConfirm(tx, [payer, unrelatedStakeKeypair]);
+
+    try {
+      await program.methods.deactivateResolutionStake().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: unrelatedStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        secondStakeAccount: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      // has_one catches the mismatched key before the staker authority check runs
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ConstraintHasOne");
+    }
+  })
+
+
+
+  it("approvers who approved are paid their reward at close", async () => {
+    const approverReward = 10_000_000;
+
+    await program.methods.initializeResolution(new anchor.BN(30), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Rewarded Approvers", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(approverReward), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(30).toArrayLike(Buffer, "le", 8)], program.programId);
+    expect(await banksClient.getBalance(newResolutionPDA)).equals(resolutionAccountRent + BigInt(2 * approverReward));
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    const closeStakeless = (approverAccounts: PublicKey[]) =>
+      program.methods.closeStakelessResolution().accounts({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+      }).remainingAccounts(approverAccounts.map((pubkey) => ({
+        isSigner: false,
+        isWritable: true,
+        pubkey,
+      }))).signers([payer]).rpc();
+
+    try {
+      await closeStakeless([approverB.publicKey]);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("MissingApproverAccount");
+    }
+
+    const approverABalanceBefore = await banksClient.getBalance(approverA.publicKey);
+    const approverBBalanceBefore = await banksClient.getBalance(approverB.publicKey);
+
+    await closeStakeless([approverA.publicKey]);
+
+    // approverB never approved, so their share went back to the owner with the rent
+    expect(await banksClient.getBalance(approverA.publicKey)).equals(approverABalanceBefore + BigInt(approverReward));
+    expect(await banksClient.getBalance(approverB.publicKey)).equals(approverBBalanceBefore);
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+  })
+
+
+
+  it("approval order follows the order approvers signed", async () => {
+    await program.methods.initializeResolution(new anchor.BN(31), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Approval Order", 3, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB, approverC].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(31).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    // signed in the reverse of the order they were added
+    for (const approver of [approverC, approverB, approverA]) {
+      await program.methods.approveResolution("").accountsStrict({
+        signer: approver.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approver]).rpc();
+    }
+
+    const expectedOrder = [approverC, approverB, approverA].map((approver) => approver.publicKey.toString());
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvedBy.map((approver) => approver.toString())).to.deep.equal(expectedOrder);
+
+    const approvalOrder: PublicKey[] = await program.methods.getApprovalOrder().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).view();
+    expect(approvalOrder.map((approver) => approver.toString())).to.deep.equal(expectedOrder);
+  })
+
+
+
+  it("details attached in chunks and closed with the resolution", async () => {
+    await program.methods.initializeResolution(new anchor.BN(32), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "With Details", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(32).toArrayLike(Buffer, "le", 8)], program.programId);
+    const [detailsPDA,] = PublicKey.findProgramAddressSync([Buffer.from("details"), newResolutionPDA.toBuffer()], program.programId);
+
+    await program.methods.attachDetails("a".repeat(900)).accounts({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([payer]).rpc();
+
+    await program.methods.appendDetails("b".repeat(900)).accounts({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([payer]).rpc();
+
+    const details = await program.account.resolutionDetails.fetch(detailsPDA);
+    expect(details.text).to.equal("a".repeat(900) + "b".repeat(900));
+    expect((await program.account.resolutionAccount.fetch(newResolutionPDA)).hasDetails).to.be.true;
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    try {
+      await program.methods.closeStakelessResolution().accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        details: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("MissingDetailsAccount");
+    }
+
+    await program.methods.closeStakelessResolution().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      details: detailsPDA,
+    }).signers([payer]).rpc();
+
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+    expect(await banksClient.getAccount(detailsPDA)).to.be.null;
+  })
+
+  it("registered approvers are required when the flag is set", async () => {
+    const [registryPDA,] = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId);
+
+    await program.methods.addToRegistry(approverA.publicKey).accounts({
+      authority: payer.publicKey,
+    }).signers([payer]).rpc();
+    expect((await program.account.registry.fetch(registryPDA)).approvers.map((approver) => approver.toBase58())).to.deep.equal([approverA.publicKey.toBase58()]);
+
+    const initRegistered = (approvers: Keypair[]) =>
+      program.methods.initializeResolution(new anchor.BN(33), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Registered Approvers", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), true, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: null,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts(approvers.map((approver) => ({
+        isSigner: false,
+        isWritable: false,
+        pubkey: approver.publicKey,
+      }))).signers([payer]).rpc();
+
+    try {
+      await initRegistered([approverA, approverB]);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ApproverNotRegistered");
+    }
+
+    await initRegistered([approverA]);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(33).toArrayLike(Buffer, "le", 8)], program.programId);
+    expect((await program.account.resolutionAccount.fetch(newResolutionPDA)).requireRegisteredApprovers).to.be.true;
+
+    try {
+      await program.methods.replaceApprover(approverA.publicKey, approverB.publicKey).accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        registry: registryPDA,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ApproverNotRegistered");
+    }
+
+    await program.methods.removeFromRegistry(approverA.publicKey).accounts({
+      authority: payer.publicKey,
+    }).signers([payer]).rpc();
+    expect((await program.account.registry.fetch(registryPDA)).approvers).to.be.empty;
+  })
+
+
+  it("is approver for members and non-members", async () => {
+    await program.methods.initializeResolution(new anchor.BN(34), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Approver Lookup", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(34).toArrayLike(Buffer, "le", 8)], program.programId);
+    const isApprover = (target: PublicKey): Promise<boolean> =>
+      program.methods.isApprover(target).accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).view();
+
+    expect(await isApprover(approverA.publicKey)).to.be.true;
+    expect(await isApprover(approverB.publicKey)).to.be.true;
+    expect(await isApprover(approverC.publicKey)).to.be.false;
+    expect(await isApprover(payer.publicKey)).to.be.false;
+  })
+
+
+  it("renew resolution carries the goal over", async () => {
+    await program.methods.initializeResolution(new anchor.BN(35), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Renewable Goal", 1, charity.publicKey, 0, 3, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer]).rpc();
+
+    const [previousResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(35).toArrayLike(Buffer, "le", 8)], program.programId);
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: previousResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    await program.methods.renewResolution(new anchor.BN(36), new anchor.BN(0), new anchor.BN(30 * 24 * 60 * 60)).accounts({
+      base: {
+        owner: payer.publicKey,
+        stakeAccount: null,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      },
+      previousResolution: previousResolutionPDA,
+    }).signers([payer]).rpc();
+
+    const [renewedResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(36).toArrayLike(Buffer, "le", 8)], program.programId);
+    const previous = await program.account.resolutionAccount.fetch(previousResolutionPDA);
+    const renewed = await program.account.resolutionAccount.fetch(renewedResolutionPDA);
+    expect(renewed.text).to.equal("Renewable Goal");
+    expect(renewed.category).to.equal(3);
+    expect(renewed.approvers.map((approver) => approver.toBase58())).to.deep.equal([approverA.publicKey.toBase58(), approverB.publicKey.toBase58()]);
+    expect(renewed.approvedBy).to.be.empty;
+    expect(renewed.endTime.sub(renewed.startTime).toNumber()).to.equal(30 * 24 * 60 * 60);
+    expect(renewed.id.toNumber()).to.equal(previous.id.toNumber() + 1);
+  })
+
+
+  it("stake lockup mirrors the end time across extensions", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(37), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Consistent Lockup", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(37).toArrayLike(Buffer, "le", 8)], program.programId);
+    const verifyLockupConsistency = (): Promise<anchor.BN> =>
+      program.methods.verifyLockupConsistency().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        secondStakeAccount: null,
+      }).view();
+
+    const before = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect((await verifyLockupConsistency()).toNumber()).to.equal(before.endTime.toNumber());
+
+    await program.methods.extendLockup(new anchor.BN(24 * 60 * 60)).accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      custodian: null,
+    }).signers([payer]).rpc();
+
+    const after = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(after.endTime.toNumber()).to.equal(before.endTime.toNumber() + 24 * 60 * 60);
+    expect((await verifyLockupConsistency()).toNumber()).to.equal(after.endTime.toNumber());
+  })
+
+
+  it("owner cancels a pending extension", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const extensionSeconds = 24 * 60 * 60;
+
+    await program.methods.initializeResolution(new anchor.BN(38), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Cancelled Extension", 2, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer, newStakeKeypair]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(38).toArrayLike(Buffer, "le", 8)], program.programId);
+    const before = await program.account.resolutionAccount.fetch(newResolutionPDA);
+
+    // nothing pending on a resolution nobody proposed an extension for
+    const [stakelessResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(36).toArrayLike(Buffer, "le", 8)], program.programId);
+    try {
+      await program.methods.cancelExtension().accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: stakelessResolutionPDA,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("NoPendingExtension");
+    }
+
+    await program.methods.approveExtension(new anchor.BN(extensionSeconds)).accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      custodian: null,
+    }).signers([approverA]).rpc();
+    expect((await program.account.resolutionAccount.fetch(newResolutionPDA)).pendingExtension.toNumber()).to.equal(extensionSeconds);
+
+    await program.methods.cancelExtension().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([payer]).rpc();
+
+    const after = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(after.pendingExtension.toNumber()).to.equal(0);
+    expect(after.extensionApprovedBy).to.be.empty;
+    expect(after.endTime.toNumber()).to.equal(before.endTime.toNumber());
+  })
+
+
+  it("resolution summary has a fixed versioned layout", async () => {
+    // approved by approverA in the renew test, which is enough for its threshold of one
+    const [summaryResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(35).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(summaryResolutionPDA);
+
+    const summary: Buffer = await program.methods.getResolutionSummary().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: summaryResolutionPDA,
+    }).view();
+
+    expect(summary.length).to.equal(51);
+    expect(summary[0]).to.equal(1);
+    expect(new PublicKey(summary.subarray(1, 33)).toString()).to.equal(payer.publicKey.toString());
+    expect(summary.readBigUInt64LE(33).toString()).to.equal(resolutionAccount.stakeAmount.toString());
+    expect(summary.readBigInt64LE(41).toString()).to.equal(resolutionAccount.endTime.toString());
+    expect(summary[49]).to.equal(1);
+    // ResolutionStatus::ActiveApproved
+    expect(summary[50]).to.equal(1);
+  })
+
+
+  it("milestones are validated and approved before release", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const now = Number((await banksClient.getClock()).unixTimestamp);
+    const milestone = new anchor.BN(now + 2 * 24 * 60 * 60);
+
+    const initWithMilestones = (milestoneReleases: anchor.BN[]) =>
+      program.methods.initializeResolution(new anchor.BN(39), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Milestones", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [milestone], milestoneReleases, [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+
+    // releases can't add up to more than the stake
+    try {
+      await initWithMilestones([new anchor.BN(6_000_000_000)]);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidMilestones");
+    }
+
+    await initWithMilestones([new anchor.BN(1_000_000_000)]);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(39).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    try {
+      await program.methods.releaseMilestone().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        custodian: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("MilestoneNotReached");
+    }
+
+    const approveMilestone = () =>
+      program.methods.approveMilestone().accountsStrict({
+        signer: approverA.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approverA]).rpc();
+
+    await approveMilestone();
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.milestones.map((time) => time.toNumber())).to.deep.equal([milestone.toNumber()]);
+    expect(resolutionAccount.milestonesReleased).to.equal(0);
+    expect(resolutionAccount.milestoneApprovedBy.map((approver) => approver.toString())).to.deep.equal([approverA.publicKey.toString()]);
+  })
+
+
+  it("approval signed off-chain is submitted by the owner", async () => {
+    await program.methods.initializeResolution(new anchor.BN(40), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Gasless Approval", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer]).rpc();
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(40).toArrayLike(Buffer, "le", 8)], program.programId);
+    const note = "checked in person";
+    // resolution || approval_nonce (u64 LE) || approved_at (i64 LE, 0 before a first approval) || note
+    const message = Buffer.concat([newResolutionPDA.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8), new anchor.BN(0).toArrayLike(Buffer, "le", 8), Buffer.from(note)]);
+
+    const approveWithSignature = (approver: PublicKey, signer: Keypair) =>
+      program.methods.approveWithSignature(approver, note).accountsStrict({
+        payer: payer.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      }).preInstructions([
+        anchor.web3.Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message }),
+      ]).signers([payer]).rpc();
+
+    // approverB's signature doesn't vouch for approverA
+    try {
+      await approveWithSignature(approverA.publicKey, approverB);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidResolutionSignature");
+    }
+
+    await approveWithSignature(approverA.publicKey, approverA);
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvedBy.map((approver) => approver.toString())).to.deep.equal([approverA.publicKey.toString()]);
+    expect(resolutionAccount.approvalNotes).to.deep.equal([note]);
+  })
+
+
+  it("creation cooldown rejects a rapid second resolution", async () => {
+    const [configPDA,] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const [profilePDA,] = PublicKey.findProgramAddressSync([Buffer.from("profile"), payer.publicKey.toBuffer()], program.programId);
+
+    const setCreationCooldown = (cooldown: number) =>
+      program.methods.setCreationCooldown(new anchor.BN(cooldown)).accountsStrict({
+        authority: payer.publicKey,
+        config: configPDA,
+      }).signers([payer]).rpc();
+
+    const initialize = (nonce: number) =>
+      program.methods.initializeResolution(new anchor.BN(nonce), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Cooldown", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: null,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer]).rpc();
+
+    await setCreationCooldown(60);
+    await initialize(41);
+
+    const profile = await program.account.ownerProfile.fetch(profilePDA);
+    expect(profile.owner.toString()).to.equal(payer.publicKey.toString());
+
+    try {
+      await initialize(42);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("CreationCooldown");
+    }
+
+    // once the cooldown has passed the owner can create again
+    const currentClock = await banksClient.getClock();
+    await setClockTimestamp(context, Number(currentClock.unixTimestamp) + 60);
+    await initialize(42);
+
+    await setCreationCooldown(0);
+  })
+
+
+  it("delegate approves on the approver's behalf until revoked", async () => {
+    const revokedDelegate = Keypair.generate();
+    const delegate = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(43).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(43), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Delegated Approval", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([approverA, approverB].map((approver) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: approver.publicKey,
+    }))).signers([payer]).rpc();
+
+    const setApprovalDelegate = (delegate: PublicKey | null) =>
+      program.methods.setApprovalDelegate(delegate).accountsStrict({
+        signer: approverA.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approverA]).rpc();
+
+    const approveAsDelegate = (signer: Keypair) =>
+      program.methods.approveResolution("on vacation").accountsStrict({
+        signer: signer.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([signer]).rpc();
+
+    // the owner can't stand in for an approver
+    try {
+      await setApprovalDelegate(payer.publicKey);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidDelegate");
+    }
+
+    await setApprovalDelegate(revokedDelegate.publicKey);
+    let resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvalDelegates.map((entry) => entry.delegate.toString())).to.deep.equal([revokedDelegate.publicKey.toString()]);
+
+    await setApprovalDelegate(null);
+
+    try {
+      await approveAsDelegate(revokedDelegate);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidApprover");
+    }
+
+    await setApprovalDelegate(delegate.publicKey);
+    await approveAsDelegate(delegate);
+
+    // the approval is recorded under the approver, not the delegate
+    resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvedBy.map((approver) => approver.toString())).to.deep.equal([approverA.publicKey.toString()]);
+  })
+
+
+  it("accrued rewards count only the surplus over rent and principal", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(44).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(44), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Accrued Rewards", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const accruedRewards = async () => {
+      const simulation = await program.methods.getAccruedRewards().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).simulate();
+      const log = simulation.raw.find((line) => line.startsWith("Program log: Accrued rewards: "));
+      return BigInt(log.split(": ").pop());
+    };
+
+    expect(await accruedRewards()).equals(0n);
+
+    // Simulate an epoch's worth of rewards landing on the stake account
+    const reward = 1_234_567n;
+    const accountInfo = await banksClient.getAccount(newStakeKeypair.publicKey);
+    context.setAccount(newStakeKeypair.publicKey, { ...accountInfo, lamports: accountInfo.lamports + Number(reward) });
+
+    expect(await accruedRewards()).equals(reward);
+  })
+
+
+  it("two-phase close waits for the deactivation epoch", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(45).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(45), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Two-Phase Close", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    const finalizeClose = () =>
+      program.methods.finalizeClose().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        secondStakeAccount: null,
+        charity: charity.publicKey,
+        recipient: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        incineratorAccount: incineratorPubkey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        custodian: null,
+        details: null,
+        vault: null,
+      }).signers([payer]).rpc();
+
+    try {
+      await finalizeClose();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("CloseNotRequested");
+    }
+
+    await program.methods.initiateClose().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    const currentClock = await banksClient.getClock();
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.closeRequestedAt.toString()).to.equal(currentClock.unixTimestamp.toString());
+    expect(resolutionAccount.closeRequestedEpoch.toString()).to.equal(currentClock.epoch.toString());
+
+    try {
+      await finalizeClose();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("DeactivationEpochNotReached");
+    }
+
+    context.setClock(new Clock(
+      currentClock.slot,
+      currentClock.epochStartTimestamp,
+      currentClock.epoch + 1n,
+      currentClock.leaderScheduleEpoch,
+      currentClock.unixTimestamp
+    ));
+
+    await finalizeClose();
+
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+    expect(await banksClient.getBalance(newStakeKeypair.publicKey)).equals(0n);
+  })
+
+
+  it("migrate moves a first release resolution to the current layout", async () => {
+    const [legacyPDA, legacyBump] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer()], program.programId);
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(46).toArrayLike(Buffer, "le", 8)], program.programId);
+    const legacyStakeKeypair = Keypair.generate();
+    const stakeAmount = 5_000_000_000;
+    const now = Number((await banksClient.getClock()).unixTimestamp);
+    const endTime = now + 365 * 24 * 60 * 60;
+
+    // the first release made the PDA staker and custodian and the owner withdrawer
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(
+      anchor.web3.StakeProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        stakePubkey: legacyStakeKeypair.publicKey,
+        authorized: new anchor.web3.Authorized(legacyPDA, payer.publicKey),
+        lockup: new anchor.web3.Lockup(endTime, 0, legacyPDA),
+        lamports: Number(stakeAccountRent) + stakeAmount,
+      }),
+    ), [payer, legacyStakeKeypair]);
+
+    // the first release's layout: owner, text (max 256), approvers and approved_by (max 3
+    // each), stake_amount, stake_account, start_time, end_time and bump, zero padded
+    const legacyData = Buffer.alloc(557);
+    let offset = 0;
+    const write = (bytes: Buffer) => { bytes.copy(legacyData, offset); offset += bytes.length; };
+    const u32 = (value: number) => { const bytes = Buffer.alloc(4); bytes.writeUInt32LE(value); return bytes; };
+    write(Buffer.from(IDL.accounts.find((account) => account.name === "ResolutionAccount").discriminator));
+    write(payer.publicKey.toBuffer());
+    write(u32(4));
+    write(Buffer.from("Gym!"));
+    write(u32(3));
+    [approverA, approverB, approverC].forEach((approver) => write(approver.publicKey.toBuffer()));
+    write(u32(1));
+    write(approverA.publicKey.toBuffer());
+    write(new anchor.BN(stakeAmount).toArrayLike(Buffer, "le", 8));
+    write(legacyStakeKeypair.publicKey.toBuffer());
+    write(new anchor.BN(now).toArrayLike(Buffer, "le", 8));
+    write(new anchor.BN(endTime).toArrayLike(Buffer, "le", 8));
+    write(Buffer.from([legacyBump]));
+
+    const rent = await banksClient.getRent();
+    context.setAccount(legacyPDA, {
+      lamports: Number(rent.minimumBalance(557n)),
+      data: legacyData,
+      owner: program.programId,
+      executable: false,
+    });
+
+    await program.methods.migrateResolution(new anchor.BN(46)).accountsStrict({
+      owner: payer.publicKey,
+      legacyResolution: legacyPDA,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      stakeAccount: legacyStakeKeypair.publicKey,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      systemProgram: anchor.web3.SystemProgram.programId,
+    }).signers([payer]).rpc();
+
+    expect(await banksClient.getAccount(legacyPDA)).to.be.null;
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.text).to.equal("Gym!");
+    expect(resolutionAccount.nonce.toNumber()).to.equal(46);
+    expect(resolutionAccount.requiredApprovals).to.equal(3);
+    expect(resolutionAccount.approvedBy.map((approver) => approver.toString())).to.deep.equal([approverA.publicKey.toString()]);
+    expect(resolutionAccount.endTime.toNumber()).to.equal(endTime);
+    expect(resolutionAccount.withdrawDestination.toString()).to.equal(payer.publicKey.toString());
+
+    // staker sits after the enum tag and rent reserve, the custodian after the lockup times
+    const stakeData = Buffer.from((await banksClient.getAccount(legacyStakeKeypair.publicKey)).data);
+    expect(new PublicKey(stakeData.subarray(12, 44)).toString()).to.equal(newResolutionPDA.toString());
+    expect(new PublicKey(stakeData.subarray(44, 76)).toString()).to.equal(payer.publicKey.toString());
+    expect(new PublicKey(stakeData.subarray(92, 124)).toString()).to.equal(newResolutionPDA.toString());
+
+    // the migrated resolution takes approvals like any other
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverB.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverB]).rpc();
+    expect((await program.account.resolutionAccount.fetch(newResolutionPDA)).approvedBy.length).to.equal(2);
+  })
+
+
+  it("add approvers after init up to the cap", async () => {
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(47).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(47), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Partial Approvers", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+
+    const addApprover = (approver: PublicKey) =>
+      program.methods.addApprover(approver).accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        registry: null,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      }).signers([payer]).rpc();
+
+    await addApprover(approverB.publicKey);
+
+    let resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvers.map((approver) => approver.toString())).to.deep.equal([approverA.publicKey.toString(), approverB.publicKey.toString()]);
+    expect(resolutionAccount.requiredApprovals).to.equal(2);
+
+    // uniqueness and owner exclusion still hold
+    for (const approver of [approverA.publicKey, payer.publicKey]) {
+      try {
+        await addApprover(approver);
+        assert.fail("Expected an error to be thrown");
+      }
+      catch (error) {
+        expect(error).to.be.instanceOf(AnchorError);
+        expect(error.error.errorCode.code).to.equal("InvalidApprover");
+      }
+    }
+
+    await addApprover(approverC.publicKey);
+    await addApprover(Keypair.generate().publicKey);
+    await addApprover(Keypair.generate().publicKey);
+
+    try {
+      await addApprover(Keypair.generate().publicKey);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ApproverListFull");
+    }
+
+    resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvers.length).to.equal(5);
+    expect(resolutionAccount.requiredApprovals).to.equal(5);
+  })
+
+
+  it("approvers drawn from the registry", async () => {
+    const [registryPDA,] = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId);
+    const pool = [approverB.publicKey, approverC.publicKey, Keypair.generate().publicKey];
+
+    for (const approver of pool) {
+      await program.methods.addToRegistry(approver).accounts({
+        authority: payer.publicKey,
+      }).signers([payer]).rpc();
+    }
+
+    const initRandom = (nonce: number, numApprovers: number) =>
+      program.methods.initializeResolutionRandom(new anchor.BN(nonce), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Random Approvers", numApprovers, 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], false).accounts({
+        base: {
+          owner: payer.publicKey,
+          stakeAccount: null,
+          validatorVoteAccount: voteAccountPubkey,
+          stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+          stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+        },
+        slotHashes: anchor.web3.SYSVAR_SLOT_HASHES_PUBKEY,
+      }).signers([payer]).rpc();
+
+    try {
+      await initRandom(48, pool.length + 1);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ApproverPoolTooSmall");
+    }
+
+    await initRandom(49, 2);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(49).toArrayLike(Buffer, "le", 8)], program.programId);
+    const approvers = (await program.account.resolutionAccount.fetch(newResolutionPDA)).approvers.map((approver) => approver.toBase58());
+    expect(approvers).to.have.lengthOf(2);
+    expect(new Set(approvers).size).to.equal(2);
+    expect(pool.map((approver) => approver.toBase58())).to.include.members(approvers);
+
+    for (const approver of pool) {
+      await program.methods.removeFromRegistry(approver).accounts({
+        authority: payer.publicKey,
+      }).signers([payer]).rpc();
+    }
+    expect((await program.account.registry.fetch(registryPDA)).approvers).to.be.empty;
+  })
+
+
+  it("orphaned resolution closes once its stake account is gone", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(50).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(50), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Orphaned", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const closeOrphaned = (stakeAccount: PublicKey, resolutionAccount: PublicKey) =>
+      program.methods.closeOrphanedResolution().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount,
+        resolutionAccount,
+        secondStakeAccount: null,
+        stats: statsPDA,
+        details: null,
+        vault: null,
+      }).signers([payer]).rpc();
+
+    // a live stake account has to go through close_resolution
+    try {
+      await closeOrphaned(maxLockupStakeKeypair.publicKey, maxLockupResolutionPDA);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("StakeAccountNotClosed");
+    }
+
+    // Simulate the stake being withdrawn out-of-band: the runtime garbage collects the
+    // emptied account, leaving the address system-owned
+    context.setAccount(newStakeKeypair.publicKey, {
+      lamports: 0,
+      data: Buffer.alloc(0),
+      owner: anchor.web3.SystemProgram.programId,
+      executable: false,
+    });
+
+    try {
+      await program.methods.deactivateResolutionStake().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        secondStakeAccount: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("StakeAccountClosed");
+    }
+
+    const activeCountBefore = (await program.account.stats.fetch(statsPDA)).activeCount;
+    const payerBalanceBefore = await banksClient.getBalance(payer.publicKey);
+
+    await closeOrphaned(newStakeKeypair.publicKey, newResolutionPDA);
+
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+    expect(await banksClient.getBalance(payer.publicKey)).equals(payerBalanceBefore + resolutionAccountRent - txFees);
+    expect((await program.account.stats.fetch(statsPDA)).activeCount.toNumber()).to.equal(activeCountBefore.toNumber() - 1);
+  })
+
+
+  it("stale approvals stop counting after approval_validity", async () => {
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(51).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(51), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Approval Expiry", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+
+    await program.methods.setApprovalValidity(new anchor.BN(60)).accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([payer]).rpc();
+
+    const approve = (note: string) =>
+      program.methods.approveResolution(note).accountsStrict({
+        signer: approverA.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approverA]).rpc();
+
+    const getStatus = () =>
+      program.methods.getStatus().accountsStrict({
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).view();
+
+    await approve("first");
+    expect(await getStatus()).to.deep.equal({ activeApproved: {} });
+
+    const currentClock = await banksClient.getClock();
+    await setClockTimestamp(context, Number(currentClock.unixTimestamp) + 61);
+    expect(await getStatus()).to.deep.equal({ activeLocked: {} });
+
+    // the approver renews the stale approval in place
+    await approve("still on track");
+    expect(await getStatus()).to.deep.equal({ activeApproved: {} });
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvedBy.length).to.equal(1);
+    expect(resolutionAccount.approvalNotes).to.deep.equal(["still on track"]);
+  })
+
+
+  it("initialize resolution with a PDA stake account", async () => {
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(52).toArrayLike(Buffer, "le", 8)], program.programId);
+    const [stakePDA, stakeBump] = PublicKey.findProgramAddressSync([Buffer.from("stake"), newResolutionPDA.toBuffer()], program.programId);
+
+    // no stake keypair to generate or sign with
+    await program.methods.initializeResolution(new anchor.BN(52), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "PDA Stake", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      stakePda: stakePDA,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.stakeAccount.toString()).to.equal(stakePDA.toString());
+    expect(resolutionAccount.stakeBump).to.equal(stakeBump);
+
+    const stakeAccount = await banksClient.getAccount(stakePDA);
+    expect(stakeAccount.owner.toString()).to.equal("Stake11111111111111111111111111111111111111");
+    expect(BigInt(stakeAccount.lamports)).equals(5_000_000_000n + stakeAccountRent);
+  })
+
+
+  it("close in the same transaction as a deactivation is rejected", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(53).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(53), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Conflicting Instructions", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    const deactivate = await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).instruction();
+
+    try {
+      await program.methods.closeResolution().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        secondStakeAccount: null,
+        charity: charity.publicKey,
+        recipient: null,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        incineratorAccount: incineratorPubkey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        custodian: null,
+        details: null,
+        vault: null,
+      }).preInstructions([deactivate]).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ConflictingInstructions");
+    }
+
+    // the whole transaction was rolled back, so the stake is still delegated
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.stakeAccount.toString()).to.equal(newStakeKeypair.publicKey.toString());
+  })
+
+
+  it("vault streams an approved payout", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const beneficiary = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(54).toArrayLike(Buffer, "le", 8)], program.programId);
+    const [vaultPDA,] = PublicKey.findProgramAddressSync([Buffer.from("vault"), newResolutionPDA.toBuffer()], program.programId);
+    const duration = 30 * 24 * 60 * 60;
+
+    await program.methods.initializeResolution(new anchor.BN(54), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Vault", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    await program.methods.openVault(new anchor.BN(duration)).accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+      vault: vaultPDA,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    }).signers([payer]).rpc();
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    const closeAccounts = {
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: beneficiary.publicKey,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    };
+
+    try {
+      await program.methods.deactivateAndClose().accountsStrict(closeAccounts).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("MissingVaultAccount");
+    }
+
+    // still in the activation epoch, so the whole stake is withdrawable right away
+    await program.methods.deactivateAndClose().accountsStrict({ ...closeAccounts, vault: vaultPDA }).signers([payer]).rpc();
+
+    const payout = 5_000_000_000n + stakeAccountRent;
+    let vault = await program.account.vault.fetch(vaultPDA);
+    expect(vault.beneficiary.toString()).to.equal(beneficiary.publicKey.toString());
+    expect(vault.totalAmount.toString()).to.equal(payout.toString());
+    expect(await banksClient.getBalance(beneficiary.publicKey)).equals(0n);
+
+    const claimVault = () =>
+      program.methods.claimVault().accountsStrict({
+        beneficiary: beneficiary.publicKey,
+        owner: payer.publicKey,
+        vault: vaultPDA,
+      }).signers([beneficiary]);
+
+    await setClockTimestamp(context, vault.startTime.toNumber() + duration / 4);
+    await claimVault().rpc();
+
+    vault = await program.account.vault.fetch(vaultPDA);
+    expect(vault.claimedAmount.toString()).to.equal((payout / 4n).toString());
+    expect(await banksClient.getBalance(beneficiary.publicKey)).equals(payout / 4n);
+
+    // the compute budget instruction keeps this transaction distinct from the first claim
+    await setClockTimestamp(context, vault.startTime.toNumber() + duration);
+    await claimVault().preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 })]).rpc();
+
+    expect(await banksClient.getBalance(beneficiary.publicKey)).equals(payout);
+    expect(await banksClient.getAccount(vaultPDA)).to.be.null;
+  })
+
+
+  it("flagged resolution holds back the approved close", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(55).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(55), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Flagged", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverB.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    const flagAccounts = (approver: Keypair) => ({
+      signer: approver.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    });
+
+    await program.methods.flagResolution().accountsStrict(flagAccounts(approverB)).signers([approverB]).rpc();
+
+    let resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.flagged).to.be.true;
+    expect(resolutionAccount.flaggedBy.toString()).to.equal(approverB.publicKey.toString());
+
+    try {
+      await program.methods.flagResolution().accountsStrict(flagAccounts(approverA)).signers([approverA]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("AlreadyFlagged");
+    }
+
+    const closeAccounts = {
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    };
+
+    // the flag is checked ahead of the stake's deactivation
+    try {
+      await program.methods.closeResolution().accountsStrict(closeAccounts).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ResolutionFlagged");
+    }
+
+    // nor can the approved stake leave bit by bit
+    try {
+      await program.methods.partialWithdraw(new anchor.BN(1_000_000_000)).accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        stats: statsPDA,
+        withdrawDestination: payer.publicKey,
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        custodian: null,
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ResolutionFlagged");
+    }
+
+    try {
+      await program.methods.clearFlag().accountsStrict(flagAccounts(approverA)).signers([approverA]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("NotFlagged");
+    }
+
+    await program.methods.clearFlag().accountsStrict(flagAccounts(approverB)).signers([approverB]).rpc();
+
+    resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.flagged).to.be.false;
+
+    // the owner settles the next dispute by going back to the approvers. The compute budget
+    // instructions keep these apart from the identical transactions sent earlier
+    await program.methods.flagResolution().accountsStrict(flagAccounts(approverA)).preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 })]).signers([approverA]).rpc();
+    await program.methods.resolveFlag().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([payer]).rpc();
+
+    resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.flagged).to.be.false;
+    expect(resolutionAccount.approvedBy).to.be.empty;
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 })]).signers([approverA]).rpc();
+
+    await program.methods.deactivateAndClose().accountsStrict(closeAccounts).signers([payer]).rpc();
+
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+  })
+
+
+  it("close events report the path taken", async () => {
+    const eventParser = new anchor.EventParser(program.programId, program.coder);
+
+    // sent straight through the banks client to get at the logs
+    const closedEvent = async (method: { transaction: () => Promise<anchor.web3.Transaction> }) => {
+      const tx = await method.transaction();
+      [tx.recentBlockhash] = await banksClient.getLatestBlockhash();
+      tx.feePayer = payer.publicKey;
+      tx.sign(payer);
+      const meta = await banksClient.processTransaction(tx);
+      return [...eventParser.parseLogs(meta.logMessages)].find((event) => event.name === "resolutionClosed").data;
+    };
+
+    const initialize = async (nonce: number, stakeKeypair: Keypair | null, lockupDuration: number) => {
+      const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(nonce).toArrayLike(Buffer, "le", 8)], program.programId);
+
+      await program.methods.initializeResolution(new anchor.BN(nonce), new anchor.BN(stakeKeypair ? 5_000_000_000 : 0), new anchor.BN(lockupDuration), "Goal", "Close Events", 1, charity.publicKey, 1_000, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: stakeKeypair ? stakeKeypair.publicKey : null,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers(stakeKeypair ? [payer, stakeKeypair] : [payer]).rpc();
+
+      return newResolutionPDA;
+    };
+
+    const closeAccounts = (stakeKeypair: Keypair, resolutionAccount: PublicKey) => ({
+      owner: payer.publicKey,
+      stakeAccount: stakeKeypair.publicKey,
+      resolutionAccount,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    });
+
+    // approved: the whole stake account goes out and the penalty doesn't apply
+    const approvedStakeKeypair = Keypair.generate();
+    const approvedResolutionPDA = await initialize(56, approvedStakeKeypair, 365 * 24 * 60 * 60);
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: approvedResolutionPDA,
+    }).signers([approverA]).rpc();
+
+    const approvedEvent = await closedEvent(program.methods.deactivateAndClose().accountsStrict(closeAccounts(approvedStakeKeypair, approvedResolutionPDA)));
+    expect(approvedEvent.wasApproved).to.be.true;
+    expect(approvedEvent.withdrawAmount.toString()).to.equal((5_000_000_000n + stakeAccountRent).toString());
+    expect(approvedEvent.penaltyAmount.toString()).to.equal("0");
+
+    // not approved: the principal comes back minus the 10% penalty
+    const failedStakeKeypair = Keypair.generate();
+    const failedResolutionPDA = await initialize(57, failedStakeKeypair, 24 * 60 * 60);
+    const stakelessResolutionPDA = await initialize(58, null, 24 * 60 * 60);
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(failedResolutionPDA);
+    await setClockTimestamp(context, resolutionAccount.endTime.toNumber() + 1);
+
+    // deactivated in its activation epoch, so the stake is withdrawable right away
+    await program.methods.deactivateResolutionStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: failedStakeKeypair.publicKey,
+      resolutionAccount: failedResolutionPDA,
+      secondStakeAccount: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    const failedEvent = await closedEvent(program.methods.closeResolution().accountsStrict(closeAccounts(failedStakeKeypair, failedResolutionPDA)));
+    expect(failedEvent.wasApproved).to.be.false;
+    expect(failedEvent.withdrawAmount.toString()).to.equal("4500000000");
+    expect(failedEvent.penaltyAmount.toString()).to.equal("500000000");
+
+    const stakelessEvent = await closedEvent(program.methods.closeStakelessResolution().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: stakelessResolutionPDA,
+      stats: statsPDA,
+      details: null,
+    }));
+    expect(stakelessEvent.wasApproved).to.be.false;
+    expect(stakelessEvent.withdrawAmount.toString()).to.equal("0");
+    expect(stakelessEvent.penaltyAmount.toString()).to.equal("0");
+  })
+
+  it("added approver's reward is escrowed so an approved close pays everyone", async () => {
+    const approverReward = 10_000_000;
+
+    await program.methods.initializeResolution(new anchor.BN(67), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Added Approver Reward", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(approverReward), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(67).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.addApprover(approverB.publicKey).accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+      registry: null,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    }).signers([payer]).rpc();
+    expect(await banksClient.getBalance(newResolutionPDA)).equals(resolutionAccountRent + BigInt(2 * approverReward));
+
+    for (const approver of [approverA, approverB]) {
+      await program.methods.approveResolution("").accountsStrict({
+        signer: approver.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approver]).rpc();
+    }
+
+    const approverABalanceBefore = await banksClient.getBalance(approverA.publicKey);
+    const approverBBalanceBefore = await banksClient.getBalance(approverB.publicKey);
+
+    await program.methods.closeStakelessResolution().accounts({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+    }).remainingAccounts([approverA.publicKey, approverB.publicKey].map((pubkey) => ({
+      isSigner: false,
+      isWritable: true,
+      pubkey,
+    }))).signers([payer]).rpc();
+
+    expect(await banksClient.getBalance(approverA.publicKey)).equals(approverABalanceBefore + BigInt(approverReward));
+    expect(await banksClient.getBalance(approverB.publicKey)).equals(approverBBalanceBefore + BigInt(approverReward));
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+  })
+
+  it("stale signed approval can't be renewed by replaying its signature", async () => {
+    await program.methods.initializeResolution(new anchor.BN(68), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Signed Approval Replay", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], [], false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(68).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.setApprovalValidity(new anchor.BN(60)).accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([payer]).rpc();
+
+    const note = "checked in person";
+    const messageFor = (approvedAt: anchor.BN) =>
+      Buffer.concat([newResolutionPDA.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8), approvedAt.toArrayLike(Buffer, "le", 8), Buffer.from(note)]);
+
+    // the compute unit limit only keeps otherwise identical transactions apart
+    const approveWithSignature = (message: Buffer, units: number) =>
+      program.methods.approveWithSignature(approverA.publicKey, note).accountsStrict({
+        payer: payer.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      }).preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units }),
+        anchor.web3.Ed25519Program.createInstructionWithPrivateKey({ privateKey: approverA.secretKey, message }),
+      ]).signers([payer]).rpc();
+
+    const firstSignature = messageFor(new anchor.BN(0));
+    await approveWithSignature(firstSignature, 200_000);
+    const approvedAt = (await program.account.resolutionAccount.fetch(newResolutionPDA)).approvedAt[0];
+
+    const currentClock = await banksClient.getClock();
+    await setClockTimestamp(context, Number(currentClock.unixTimestamp) + 61);
+
+    try {
+      await approveWithSignature(firstSignature, 210_000);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidResolutionSignature");
+    }
+
+    // the approver has to sign over the approval being renewed
+    await approveWithSignature(messageFor(approvedAt), 220_000);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.approvedAt[0].gt(approvedAt)).to.be.true;
+  })
+
+
+  it("weighted approvals", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(59).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const initializeWeighted = (weights: number[]) =>
+      program.methods.initializeResolution(new anchor.BN(59), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Weighted", 70, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], Buffer.from(weights), false).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount: voteAccountPubkey,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverB.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+
+    // the weights have to add up to 100
+    try {
+      await initializeWeighted([70, 20]);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("InvalidWeights");
+    }
+
+    await initializeWeighted([70, 30]);
+
+    const approve = (approver: Keypair) =>
+      program.methods.approveResolution("").accountsStrict({
+        signer: approver.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+      }).signers([approver]).rpc();
+
+    const closeAccounts = {
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      stats: statsPDA,
+      withdrawDestination: payer.publicKey,
+      secondStakeAccount: null,
+      charity: charity.publicKey,
+      recipient: null,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      incineratorAccount: incineratorPubkey,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      custodian: null,
+      details: null,
+      vault: null,
+    };
+
+    // 30 of the required 70
+    await approve(approverB);
+
+    try {
+      await program.methods.deactivateAndClose().accountsStrict(closeAccounts).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("DeactivationPending");
+    }
+
+    await approve(approverA);
+
+    await program.methods.deactivateAndClose().accountsStrict({ ...closeAccounts, recipient: payer.publicKey }).signers([payer]).rpc();
+
+    expect(await banksClient.getAccount(newResolutionPDA)).to.be.null;
+  })
+
+
+  it("self validator is rejected when disallowed", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    // the same vote account, withdrawn by the owner
+    const selfVoteAccountPubkey = Keypair.generate().publicKey;
+    const voteAccountInfo = await banksClient.getAccount(voteAccountPubkey);
+    const selfVoteAccountData = Uint8Array.from(voteAccountInfo.data);
+    selfVoteAccountData.set(payer.publicKey.toBytes(), 4 + 32);
+    context.setAccount(selfVoteAccountPubkey, { ...voteAccountInfo, data: selfVoteAccountData });
+
+    const initialize = (validatorVoteAccount: PublicKey, disallowSelfValidator: boolean) =>
+      program.methods.initializeResolution(new anchor.BN(60), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Self Validator", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], Buffer.from([]), disallowSelfValidator).accounts({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        validatorVoteAccount,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      }).remainingAccounts([
+        {
+          isSigner: false,
+          isWritable: false,
+          pubkey: approverA.publicKey,
+        },
+      ]).signers([payer, newStakeKeypair]).rpc();
+
+    try {
+      await initialize(selfVoteAccountPubkey, true);
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("SelfValidatorNotAllowed");
+    }
+
+    await initialize(voteAccountPubkey, true);
+
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(60).toArrayLike(Buffer, "le", 8)], program.programId);
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.disallowSelfValidator).to.be.true;
+
+    // the flag sticks for later redelegations, checked before any stake CPI
+    try {
+      await program.methods.redelegateStake().accountsStrict({
+        owner: payer.publicKey,
+        stakeAccount: newStakeKeypair.publicKey,
+        resolutionAccount: newResolutionPDA,
+        validatorVoteAccount: selfVoteAccountPubkey,
+        stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+        stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+        stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      }).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("SelfValidatorNotAllowed");
+    }
+  })
+
+
+  it("split stake merges back into the main stake account", async () => {
+    const newStakeKeypair = Keypair.generate();
+    const splitStakeKeypair = Keypair.generate();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(61).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    await program.methods.initializeResolution(new anchor.BN(61), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Merge Stake", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], Buffer.from([]), false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    // same validator, so both accounts are activating on it and can merge right away
+    await program.methods.splitStake(new anchor.BN(2_000_000_000)).accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      splitStakeAccount: splitStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      systemProgram: anchor.web3.SystemProgram.programId,
+    }).signers([payer, splitStakeKeypair]).rpc();
+
+    await program.methods.mergeStake().accountsStrict({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      secondStakeAccount: splitStakeKeypair.publicKey,
+      resolutionAccount: newResolutionPDA,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      stakeHistory: anchor.web3.SYSVAR_STAKE_HISTORY_PUBKEY,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+    }).signers([payer]).rpc();
+
+    const resolutionAccount = await program.account.resolutionAccount.fetch(newResolutionPDA);
+    expect(resolutionAccount.secondStakeAccount).to.be.null;
+    expect(resolutionAccount.stakeAmount.toString()).to.equal("5000000000");
+    // the split account's rent reserve comes along with its stake
+    expect(await banksClient.getBalance(newStakeKeypair.publicKey)).equals(5_000_000_000n + 2n * stakeAccountRent);
+    expect(await banksClient.getBalance(splitStakeKeypair.publicKey)).equals(0n);
+  })
+
+
+  it("derive resolution address matches init", async () => {
+    const newStakeKeypair = Keypair.generate();
+
+    await program.methods.initializeResolution(new anchor.BN(62), new anchor.BN(5_000_000_000), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Derived Address", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], Buffer.from([]), false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: newStakeKeypair.publicKey,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer, newStakeKeypair]).rpc();
+
+    const derived = await program.methods.deriveResolutionAddress(payer.publicKey, new anchor.BN(62)).accountsStrict({}).view();
+
+    // the account init created lives at the derived address with the derived bump
+    const resolutionAccount = await program.account.resolutionAccount.fetch(derived.address);
+    expect(resolutionAccount.stakeAccount.toString()).to.equal(newStakeKeypair.publicKey.toString());
+    expect(resolutionAccount.bump).to.equal(derived.bump);
+
+    const simulation = await program.methods.deriveResolutionAddress(payer.publicKey, new anchor.BN(62)).accountsStrict({}).simulate();
+    expect(simulation.raw).to.include(`Program log: Resolution address: ${derived.address.toString()}`);
+  })
+
+  it("approve via cpi needs an allowlisted caller program", async () => {
+    const [approverProgramsPDA,] = PublicKey.findProgramAddressSync([Buffer.from("approver_programs")], program.programId);
+    const daoProgram = Keypair.generate().publicKey;
+
+    await program.methods.initializeApproverPrograms().accounts({
+      authority: payer.publicKey,
+    }).rpc();
+
+    await program.methods.addApproverProgram(daoProgram).accounts({
+      authority: payer.publicKey,
+    }).signers([payer]).rpc();
+    expect((await program.account.approverPrograms.fetch(approverProgramsPDA)).programs.map((allowed) => allowed.toBase58())).to.deep.equal([daoProgram.toBase58()]);
+
+    try {
+      await program.methods.addApproverProgram(daoProgram).accounts({
+        authority: payer.publicKey,
+      }).preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 300_000 })]).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ApproverProgramAlreadyAllowed");
+    }
+
+    await program.methods.initializeResolution(new anchor.BN(63), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Approve Via CPI", 1, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], Buffer.from([]), false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+    ]).signers([payer]).rpc();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(63).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    // signing the transaction directly is exactly what the CPI path rules out
+    try {
+      await program.methods.approveViaCpi("").accountsStrict({
+        approver: approverA.publicKey,
+        owner: payer.publicKey,
+        resolutionAccount: newResolutionPDA,
+        approverPrograms: approverProgramsPDA,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      }).signers([approverA]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("CpiRequired");
+    }
+    expect((await program.account.resolutionAccount.fetch(newResolutionPDA)).approvedBy).to.be.empty;
+
+    await program.methods.removeApproverProgram(daoProgram).accounts({
+      authority: payer.publicKey,
+    }).signers([payer]).rpc();
+    expect((await program.account.approverPrograms.fetch(approverProgramsPDA)).programs).to.be.empty;
+
+    try {
+      await program.methods.removeApproverProgram(daoProgram).accounts({
+        authority: payer.publicKey,
+      }).preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 300_000 })]).signers([payer]).rpc();
+      assert.fail("Expected an error to be thrown");
+    }
+    catch (error) {
+      expect(error).to.be.instanceOf(AnchorError);
+      expect(error.error.errorCode.code).to.equal("ApproverProgramNotAllowed");
+    }
+  })
+
+  it("approval progress at none, partial and full approval", async () => {
+    await program.methods.initializeResolution(new anchor.BN(64), new anchor.BN(0), new anchor.BN(365 * 24 * 60 * 60), "Goal", "Approval Progress", 2, charity.publicKey, 0, 0, null, null, new anchor.BN(0), null, new anchor.BN(0), false, [], [], Buffer.from([]), false).accounts({
+      owner: payer.publicKey,
+      stakeAccount: null,
+      validatorVoteAccount: voteAccountPubkey,
+      stakeProgram: new anchor.web3.PublicKey("Stake11111111111111111111111111111111111111"),
+      stakeConfig: new anchor.web3.PublicKey("StakeConfig11111111111111111111111111111111"),
+    }).remainingAccounts([
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverA.publicKey,
+      },
+      {
+        isSigner: false,
+        isWritable: false,
+        pubkey: approverB.publicKey,
+      },
+    ]).signers([payer]).rpc();
+    const [newResolutionPDA,] = PublicKey.findProgramAddressSync([Buffer.from("resolution"), payer.publicKey.toBuffer(), new anchor.BN(64).toArrayLike(Buffer, "le", 8)], program.programId);
+
+    const getApprovalProgress = () => program.methods.getApprovalProgress().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).view();
+
+    expect(await getApprovalProgress()).to.deep.equal({ approved: 0, required: 2, thresholdMet: false });
+
+    const simulation = await program.methods.getApprovalProgress().accountsStrict({
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).simulate();
+    expect(simulation.raw).to.include("Program log: Approvals: 0/2");
+    expect(simulation.raw).to.include("Program log: Threshold met: false");
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverA.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverA]).rpc();
+    expect(await getApprovalProgress()).to.deep.equal({ approved: 1, required: 2, thresholdMet: false });
+
+    await program.methods.approveResolution("").accountsStrict({
+      signer: approverB.publicKey,
+      owner: payer.publicKey,
+      resolutionAccount: newResolutionPDA,
+    }).signers([approverB]).rpc();
+    expect(await getApprovalProgress()).to.deep.equal({ approved: 2, required: 2, thresholdMet: true });
+  })
+
 });